@@ -1,8 +1,9 @@
 use clap::ValueEnum;
+use serde_json::{json, Map, Value};
 use std::{fs, string};
 
 use crate::CliError;
-use desktop_file::{desktop_entry, DesktopFile, FromRaw, Group};
+use desktop_file::{desktop_entry, DesktopFile, FromRaw, Group, Key, Locale};
 
 #[derive(ValueEnum, Debug, Clone, Copy)]
 #[value(rename_all = "PascalCase")]
@@ -18,6 +19,14 @@ enum ValueType {
     DesktopEntryExec,
 }
 
+#[derive(ValueEnum, Debug, Clone, Copy)]
+enum Format {
+    /// Human-readable output
+    Human,
+    /// JSON output
+    Json,
+}
+
 #[derive(clap::Args, Debug)]
 pub struct Args {
     /// Path to a desktop file to read
@@ -29,6 +38,25 @@ pub struct Args {
     /// Value type to interpret value as
     #[arg(default_value = "RawQuoted")]
     value_type: ValueType,
+    /// Output format
+    #[arg(long, default_value = "human")]
+    format: Format,
+    /// Locale to retrieve the value in (e.g. de_DE), falling back to less specific locales and
+    /// then the unlocalized value
+    #[arg(long, value_parser = parse_locale)]
+    locale: Option<Locale>,
+}
+
+fn parse_locale(s: &str) -> Result<Locale, String> {
+    Locale::parse(s).ok_or_else(|| format!("invalid locale {s:?}"))
+}
+
+/// The key to retrieve, localized if a locale was given.
+fn lookup_key<'a>(key: &'a str, locale: Option<&'a Locale>) -> Key<'a> {
+    match locale {
+        Some(locale) => locale.key(key).into(),
+        None => key.into(),
+    }
 }
 
 fn print_file(file: DesktopFile) -> Result<(), CliError> {
@@ -44,10 +72,107 @@ fn print_group(group_name: &str, group: &Group) -> Result<(), CliError> {
     Ok(())
 }
 
+fn print_json(value: Value) -> Result<(), CliError> {
+    println!("{value:#}");
+
+    Ok(())
+}
+
+/// An object of group names to objects of keys to raw values.
+fn file_json(file: &DesktopFile) -> Value {
+    let groups = file
+        .groups()
+        .map(|(group_name, group)| (group_name.to_string(), group_json(group)))
+        .collect::<Map<_, _>>();
+
+    Value::Object(groups)
+}
+
+/// An object of keys to raw values.
+fn group_json(group: &Group) -> Value {
+    let entries = group
+        .entries()
+        .map(|(key, value)| (key.to_string(), Value::String(value.to_string())))
+        .collect::<Map<_, _>>();
+
+    Value::Object(entries)
+}
+
+/// An object with the value's `type` (`raw`, `string`, `strings`, `boolean` or `exec`) and the
+/// `value` itself.
+fn value_json(
+    group_name: &str,
+    group: &Group,
+    key: &str,
+    locale: Option<&Locale>,
+    value_type: ValueType,
+) -> Result<Value, CliError> {
+    fn get<V: FromRaw>(
+        group_name: &str,
+        group: &Group,
+        key: &str,
+        locale: Option<&Locale>,
+    ) -> Result<V, CliError> {
+        group
+            .get(lookup_key(key, locale))
+            .ok_or_else(|| format!("could not find [{group_name}].{key}"))?
+            .map_err(|err| {
+                CliError::new(
+                    format!("could not parse [{group_name}].{key}"),
+                    err.to_string(),
+                )
+            })
+    }
+
+    let (ty, value) = match value_type {
+        ValueType::Raw | ValueType::RawQuoted => {
+            let value = group
+                .get_raw(lookup_key(key, locale))
+                .ok_or_else(|| format!("could not find [{group_name}].{key}"))?;
+
+            ("raw", json!(value))
+        }
+        ValueType::String => (
+            "string",
+            json!(get::<String>(group_name, group, key, locale)?),
+        ),
+        ValueType::Strings => (
+            "strings",
+            json!(get::<Vec<String>>(group_name, group, key, locale)?),
+        ),
+        ValueType::Boolean => (
+            "boolean",
+            json!(get::<bool>(group_name, group, key, locale)?),
+        ),
+        ValueType::DesktopEntryExec => {
+            let desktop_entry::Exec { program, arguments } = get(group_name, group, key, locale)?;
+            let arguments = arguments
+                .into_iter()
+                .map(|argument| match argument {
+                    desktop_entry::ExecArgument::String(s) => {
+                        json!({ "type": "string", "value": s })
+                    }
+                    desktop_entry::ExecArgument::FieldCode(fc) => {
+                        json!({ "type": "field-code", "value": format!("%{fc}") })
+                    }
+                })
+                .collect::<Vec<_>>();
+
+            (
+                "exec",
+                json!({ "program": program, "arguments": arguments }),
+            )
+        }
+    };
+
+    Ok(json!({ "type": ty, "value": value }))
+}
+
 fn print_value(
     group_name: &str,
     group: &Group,
     key: &str,
+    locale: Option<&Locale>,
     value_type: ValueType,
 ) -> Result<(), CliError> {
     trait ToStringFromStr {
@@ -55,9 +180,10 @@ fn print_value(
             group_name: &str,
             group: &Group,
             key: &str,
+            locale: Option<&Locale>,
         ) -> Result<(String, Option<String>), CliError> {
             let value = group
-                .get_raw(key.as_ref())
+                .get_raw(lookup_key(key, locale))
                 .ok_or_else(|| format!("could not find [{group_name}].{key}"))?;
 
             Self::to_string_from_str(value)
@@ -73,9 +199,10 @@ fn print_value(
             group_name: &str,
             group: &Group,
             key: &str,
+            locale: Option<&Locale>,
         ) -> Result<(String, Option<String>), CliError> {
             let value = group
-                .get(key.as_ref())
+                .get(lookup_key(key, locale))
                 .ok_or_else(|| format!("could not find [{group_name}].{key}"))?
                 .map_err(|err| {
                     CliError::new(
@@ -180,13 +307,13 @@ fn print_value(
     }
 
     let (value, meta) = match value_type {
-        ValueType::Raw => ValueTypeRaw::to_string(&group_name, group, &key)?,
-        ValueType::RawQuoted => ValueTypeRawQuoted::to_string(&group_name, group, &key)?,
-        ValueType::String => ValueTypeString::to_string(&group_name, group, &key)?,
-        ValueType::Strings => ValueTypeStrings::to_string(&group_name, group, &key)?,
-        ValueType::Boolean => ValueTypeBoolean::to_string(&group_name, group, &key)?,
+        ValueType::Raw => ValueTypeRaw::to_string(&group_name, group, &key, locale)?,
+        ValueType::RawQuoted => ValueTypeRawQuoted::to_string(&group_name, group, &key, locale)?,
+        ValueType::String => ValueTypeString::to_string(&group_name, group, &key, locale)?,
+        ValueType::Strings => ValueTypeStrings::to_string(&group_name, group, &key, locale)?,
+        ValueType::Boolean => ValueTypeBoolean::to_string(&group_name, group, &key, locale)?,
         ValueType::DesktopEntryExec => {
-            ValueTypeDesktopEntryExec::to_string(&group_name, group, &key)?
+            ValueTypeDesktopEntryExec::to_string(&group_name, group, &key, locale)?
         }
     };
 
@@ -209,7 +336,10 @@ pub fn main(args: Args) -> Result<(), CliError> {
 
     let (group, group_name) = match args.group_name {
         None => {
-            return print_file(file);
+            return match args.format {
+                Format::Human => print_file(file),
+                Format::Json => print_json(file_json(&file)),
+            };
         }
         Some(group_name) => {
             let group = file
@@ -220,12 +350,108 @@ pub fn main(args: Args) -> Result<(), CliError> {
         }
     };
 
-    match args.key {
-        None => {
-            return print_group(&group_name, group);
-        }
-        Some(key) => {
-            return print_value(&group_name, group, &key, args.value_type);
+    let locale = args.locale.as_ref();
+    match (args.key, args.format) {
+        (None, Format::Human) => print_group(&group_name, group),
+        (None, Format::Json) => print_json(group_json(group)),
+        (Some(key), Format::Human) => {
+            print_value(&group_name, group, &key, locale, args.value_type)
         }
+        (Some(key), Format::Json) => print_json(value_json(
+            &group_name,
+            group,
+            &key,
+            locale,
+            args.value_type,
+        )?),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn file() -> &'static str {
+        concat!(
+            "[Desktop Entry]\n",
+            "Name=Firefox\n",
+            "Keywords=web;browser;\n",
+            "Terminal=false\n",
+            "Exec=firefox --new-window %u\n",
+            "\n",
+            "[Desktop Action new-window]\n",
+            "Name=New Window\n",
+        )
+    }
+
+    #[test]
+    fn json_file() {
+        let file = DesktopFile::parse(file()).unwrap();
+
+        assert_eq!(
+            file_json(&file),
+            json!({
+                "Desktop Entry": {
+                    "Name": "Firefox",
+                    "Keywords": "web;browser;",
+                    "Terminal": "false",
+                    "Exec": "firefox --new-window %u",
+                },
+                "Desktop Action new-window": {
+                    "Name": "New Window",
+                },
+            })
+        );
+        // groups and keys keep their order
+        assert_eq!(
+            file_json(&file).to_string(),
+            concat!(
+                r#"{"Desktop Entry":{"Name":"Firefox","Keywords":"web;browser;","#,
+                r#""Terminal":"false","Exec":"firefox --new-window %u"},"#,
+                r#""Desktop Action new-window":{"Name":"New Window"}}"#,
+            )
+        );
+    }
+
+    #[test]
+    fn json_value() {
+        let file = DesktopFile::parse(file()).unwrap();
+        let group = file.group("Desktop Entry").unwrap();
+        let value =
+            |key, value_type| value_json("Desktop Entry", group, key, None, value_type).ok();
+
+        assert_eq!(
+            value("Keywords", ValueType::Raw),
+            Some(json!({ "type": "raw", "value": "web;browser;" }))
+        );
+        assert_eq!(
+            value("Name", ValueType::String),
+            Some(json!({ "type": "string", "value": "Firefox" }))
+        );
+        assert_eq!(
+            value("Keywords", ValueType::Strings),
+            Some(json!({ "type": "strings", "value": ["web", "browser"] }))
+        );
+        assert_eq!(
+            value("Terminal", ValueType::Boolean),
+            Some(json!({ "type": "boolean", "value": false }))
+        );
+        assert_eq!(
+            value("Exec", ValueType::DesktopEntryExec),
+            Some(json!({
+                "type": "exec",
+                "value": {
+                    "program": "firefox",
+                    "arguments": [
+                        { "type": "string", "value": "--new-window" },
+                        { "type": "field-code", "value": "%u" },
+                    ],
+                },
+            }))
+        );
+
+        // missing and unparseable values are errors
+        assert_eq!(value("Missing", ValueType::Raw), None);
+        assert_eq!(value("Name", ValueType::Boolean), None);
     }
 }
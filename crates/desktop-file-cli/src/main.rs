@@ -1,5 +1,8 @@
 mod desktop_entry;
 mod get;
+mod validate;
+
+use std::process;
 
 use clap::Parser;
 use colored::*;
@@ -33,12 +36,15 @@ enum Args {
     Get(get::Args),
     /// Read and dump a desktop entry file
     DesktopEntry(desktop_entry::Args),
+    /// Check a desktop entry file for problems
+    Validate(validate::Args),
 }
 
 fn main() {
     let result = match Args::parse() {
         Args::Get(args) => get::main(args),
         Args::DesktopEntry(args) => desktop_entry::main(args),
+        Args::Validate(args) => validate::main(args),
     };
 
     match result {
@@ -48,6 +54,8 @@ fn main() {
             if let Some(inner) = inner {
                 eprintln!("{}", inner);
             }
+
+            process::exit(1);
         }
     }
 }
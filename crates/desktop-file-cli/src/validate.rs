@@ -0,0 +1,195 @@
+use std::fs;
+use std::path::Path;
+
+use colored::*;
+use desktop_file::desktop_entry::{DesktopEntry, ValidationWarning};
+use desktop_file::{DesktopFile, DesktopFileError};
+
+use crate::CliError;
+
+#[derive(clap::Args, Debug)]
+pub struct Args {
+    /// Path to a desktop file to validate
+    path: std::path::PathBuf,
+}
+
+/// Keys defined by the specification for the [Desktop Entry] group.
+const KNOWN_KEYS: &[&str] = &[
+    "Type",
+    "Version",
+    "Name",
+    "GenericName",
+    "NoDisplay",
+    "Comment",
+    "Icon",
+    "Hidden",
+    "OnlyShowIn",
+    "NotShowIn",
+    "DBusActivatable",
+    "TryExec",
+    "Exec",
+    "Path",
+    "Terminal",
+    "Actions",
+    "MimeType",
+    "Categories",
+    "Implements",
+    "Keywords",
+    "StartupNotify",
+    "StartupWMClass",
+    "URL",
+    "PrefersNonDefaultGPU",
+    "SingleMainWindow",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Severity {
+    Warning,
+    Error,
+}
+
+struct Finding {
+    severity: Severity,
+    /// 1-based line number the finding refers to, if it refers to a specific line.
+    line: Option<usize>,
+    message: String,
+}
+
+impl Finding {
+    fn new(severity: Severity, line: Option<usize>, message: impl Into<String>) -> Self {
+        Self {
+            severity,
+            line,
+            message: message.into(),
+        }
+    }
+}
+
+/// Finds the 1-based line number of the first line matching `predicate`.
+fn find_line(contents: &str, predicate: impl Fn(&str) -> bool) -> Option<usize> {
+    contents
+        .lines()
+        .position(|line| predicate(line.trim_end_matches('\r')))
+        .map(|index| index + 1)
+}
+
+/// Finds the line number of the first entry for `key` (not including any locale).
+fn key_line(contents: &str, key: &str) -> Option<usize> {
+    find_line(contents, |line| {
+        line.strip_prefix(key)
+            .is_some_and(|rest| rest.trim_start_matches(' ').starts_with('='))
+    })
+}
+
+fn group_line(contents: &str, group_name: &str) -> Option<usize> {
+    find_line(contents, |line| line == format!("[{group_name}]"))
+}
+
+/// Checks the desktop file `contents` for problems, stopping at the first error which prevents the
+/// file from being read.
+fn validate(contents: &str) -> Vec<Finding> {
+    use Severity::*;
+
+    let file = match DesktopFile::parse(contents) {
+        Ok(file) => file,
+        Err(err) => {
+            let line = match &err {
+                DesktopFileError::Parse(err) => Some(err.location().0),
+                _ => err
+                    .span()
+                    .map(|span| contents[..span.start].lines().count() + 1),
+            };
+
+            return vec![Finding::new(Error, line, err.to_string())];
+        }
+    };
+
+    let desktop_entry_line = group_line(contents, "Desktop Entry");
+    let desktop_entry = match DesktopEntry::try_from_file(&file) {
+        Ok(desktop_entry) => desktop_entry,
+        Err(err) => return vec![Finding::new(Error, desktop_entry_line, err.to_string())],
+    };
+
+    let mut findings = vec![];
+
+    for warning in desktop_entry.validate() {
+        let finding = match warning {
+            ValidationWarning::RequiredKeyMissing(_) => {
+                Finding::new(Error, desktop_entry_line, warning.to_string())
+            }
+            ValidationWarning::ConflictingKeys(_, key) => {
+                Finding::new(Error, key_line(contents, key), warning.to_string())
+            }
+            ValidationWarning::EmptyValue(key) => {
+                Finding::new(Warning, key_line(contents, key), warning.to_string())
+            }
+            ValidationWarning::DeprecatedKey("Encoding") => {
+                let message = format!("{warning}, files must always be UTF-8");
+                Finding::new(Warning, key_line(contents, "Encoding"), message)
+            }
+            ValidationWarning::DeprecatedKey(key) => {
+                Finding::new(Warning, key_line(contents, key), warning.to_string())
+            }
+            ValidationWarning::InvalidValue(key) => {
+                Finding::new(Error, key_line(contents, key), warning.to_string())
+            }
+            ValidationWarning::UnsupportedVersion(_) => {
+                Finding::new(Warning, key_line(contents, "Version"), warning.to_string())
+            }
+        };
+        findings.push(finding);
+    }
+
+    for (key, _) in desktop_entry.group.entries() {
+        // localized keys are checked without their locale
+        let base_key = key.split_once('[').map_or(key, |(base_key, _)| base_key);
+
+        // the deprecated Encoding key is reported by `DesktopEntry::validate`
+        if base_key == "Encoding" {
+            continue;
+        }
+
+        if !KNOWN_KEYS.contains(&base_key) && !base_key.starts_with("X-") {
+            let message =
+                format!("unknown key {key}, extension keys must be prefixed with X- instead");
+            findings.push(Finding::new(Warning, key_line(contents, key), message));
+        }
+    }
+
+    findings.sort_by_key(|finding| finding.line);
+    findings
+}
+
+fn print_finding(path: &Path, finding: &Finding) {
+    let severity = match finding.severity {
+        Severity::Warning => "warning:".yellow(),
+        Severity::Error => "error:".red(),
+    };
+    let location = match finding.line {
+        Some(line) => format!("{}:{line}", path.display()),
+        None => format!("{}", path.display()),
+    };
+
+    println!("{location}: {severity} {}", finding.message);
+}
+
+pub fn main(args: Args) -> Result<(), CliError> {
+    let contents = fs::read_to_string(&args.path)
+        .map_err(|err| CliError::new("could not read .desktop file", err.to_string()))?;
+
+    let findings = validate(&contents);
+    for finding in &findings {
+        print_finding(&args.path, finding);
+    }
+
+    let errors = findings
+        .iter()
+        .filter(|finding| finding.severity == Severity::Error)
+        .count();
+    if errors != 0 {
+        let word = if errors == 1 { "error" } else { "errors" };
+        return Err(format!("validation failed with {errors} {word}").into());
+    }
+
+    Ok(())
+}
@@ -0,0 +1,81 @@
+use std::process::{Command, Output};
+
+fn get(fixture: &str, args: &[&str]) -> Output {
+    let path = format!("{}/tests/fixtures/{fixture}", env!("CARGO_MANIFEST_DIR"));
+
+    Command::new(env!("CARGO_BIN_EXE_desktop-file-cli"))
+        .args(["get", &path])
+        .args(args)
+        .env("NO_COLOR", "1")
+        .output()
+        .unwrap()
+}
+
+fn stdout(output: &Output) -> String {
+    String::from_utf8(output.stdout.clone()).unwrap()
+}
+
+#[test]
+fn locale() {
+    let output = get(
+        "valid.desktop",
+        &["Desktop Entry", "Name", "Raw", "--locale", "de"],
+    );
+
+    assert!(output.status.success());
+    assert_eq!(stdout(&output), "[Desktop Entry].Name\nGültig\n");
+}
+
+#[test]
+fn locale_fallback() {
+    // falls back to the less specific locale...
+    let output = get(
+        "valid.desktop",
+        &["Desktop Entry", "Name", "Raw", "--locale", "de_AT"],
+    );
+    assert!(output.status.success());
+    assert_eq!(stdout(&output), "[Desktop Entry].Name\nGültig\n");
+
+    // ... and the unlocalized value if the locale is absent
+    let output = get(
+        "valid.desktop",
+        &["Desktop Entry", "Name", "Raw", "--locale", "fr"],
+    );
+    assert!(output.status.success());
+    assert_eq!(stdout(&output), "[Desktop Entry].Name\nValid\n");
+}
+
+#[test]
+fn locale_json() {
+    let output = get(
+        "valid.desktop",
+        &[
+            "Desktop Entry",
+            "Name",
+            "string",
+            "--locale",
+            "de",
+            "--format",
+            "json",
+        ],
+    );
+
+    assert!(output.status.success());
+    let value: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(
+        value,
+        serde_json::json!({ "type": "string", "value": "Gültig" })
+    );
+}
+
+#[test]
+fn locale_invalid() {
+    let output = get(
+        "valid.desktop",
+        &["Desktop Entry", "Name", "--locale", "not a locale"],
+    );
+
+    assert!(!output.status.success());
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("invalid locale"));
+}
@@ -0,0 +1,61 @@
+use std::process::{Command, Output};
+
+fn validate(fixture: &str) -> Output {
+    let path = format!("{}/tests/fixtures/{fixture}", env!("CARGO_MANIFEST_DIR"));
+
+    Command::new(env!("CARGO_BIN_EXE_desktop-file-cli"))
+        .args(["validate", &path])
+        .env("NO_COLOR", "1")
+        .output()
+        .unwrap()
+}
+
+fn stdout_lines(output: &Output) -> Vec<String> {
+    String::from_utf8(output.stdout.clone())
+        .unwrap()
+        .lines()
+        .map(|line| {
+            // strip the directory, which depends on where the repository is
+            let (_, line) = line.split_once("fixtures/").unwrap();
+            line.to_string()
+        })
+        .collect()
+}
+
+#[test]
+fn broken() {
+    let output = validate("broken.desktop");
+
+    assert!(!output.status.success());
+    assert_eq!(
+        stdout_lines(&output),
+        [
+            "broken.desktop:1: error: the Exec key is required for this type of desktop entry",
+            "broken.desktop:3: warning: the Encoding key is deprecated, files must always be UTF-8",
+            "broken.desktop:5: warning: the Icon key must not be empty",
+            "broken.desktop:7: error: the OnlyShowIn and NotShowIn keys must not both be present",
+            "broken.desktop:9: warning: unknown key Frobnicate, extension keys must be prefixed with X- instead",
+        ]
+    );
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("validation failed with 2 errors"));
+}
+
+#[test]
+fn unparseable() {
+    let output = validate("unparseable.desktop");
+
+    assert!(!output.status.success());
+    assert_eq!(
+        stdout_lines(&output),
+        ["unparseable.desktop:4: error: parsing should succeed (failed at line 4 column 5)"]
+    );
+}
+
+#[test]
+fn valid() {
+    let output = validate("valid.desktop");
+
+    assert!(output.status.success());
+    assert!(output.stdout.is_empty());
+}
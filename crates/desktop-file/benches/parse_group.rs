@@ -0,0 +1,56 @@
+//! Compares [`DesktopFile::parse`] with [`DesktopFile::parse_group`] when only the
+//! `Desktop Entry` group is read, over a directory's worth of files with several action groups.
+
+use criterion::{criterion_group, criterion_main, Criterion};
+use desktop_file::DesktopFile;
+use std::fmt::Write;
+
+const FILES: usize = 200;
+const ACTIONS: usize = 8;
+
+fn desktop_file(n: usize) -> String {
+    let mut s = String::new();
+    writeln!(s, "[Desktop Entry]").unwrap();
+    writeln!(s, "Type=Application").unwrap();
+    writeln!(s, "Name=Application {n}").unwrap();
+    writeln!(s, "Name[de]=Anwendung {n}").unwrap();
+    writeln!(s, "Exec=app-{n} %U").unwrap();
+    let actions: Vec<_> = (0..ACTIONS).map(|i| format!("action-{i}")).collect();
+    writeln!(s, "Actions={};", actions.join(";")).unwrap();
+    for action in &actions {
+        writeln!(s).unwrap();
+        writeln!(s, "[Desktop Action {action}]").unwrap();
+        writeln!(s, "Name=Action {action}").unwrap();
+        writeln!(s, "Name[de]=Aktion {action}").unwrap();
+        writeln!(s, "Name[fr]=Action {action}").unwrap();
+        writeln!(s, "Exec=app-{n} --{action}").unwrap();
+        writeln!(s, "Icon=app-{n}-{action}").unwrap();
+    }
+    s
+}
+
+fn parse_group(c: &mut Criterion) {
+    let files: Vec<_> = (0..FILES).map(desktop_file).collect();
+
+    let mut group = c.benchmark_group("multi_group_files");
+    group.bench_function("parse", |b| {
+        b.iter(|| {
+            for file in &files {
+                let file = DesktopFile::parse(file).unwrap();
+                criterion::black_box(file.group("Desktop Entry"));
+            }
+        })
+    });
+    group.bench_function("parse_group", |b| {
+        b.iter(|| {
+            for file in &files {
+                let file = DesktopFile::parse_group(file, "Desktop Entry").unwrap();
+                criterion::black_box(file.group("Desktop Entry"));
+            }
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(benches, parse_group);
+criterion_main!(benches);
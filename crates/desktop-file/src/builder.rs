@@ -0,0 +1,163 @@
+//! Builds a [`DesktopFile`] in code, e.g. to generate a desktop entry for an installed program.
+//!
+//! # Example
+//! ```
+//! use desktop_file::builder::DesktopFileBuilder;
+//! use desktop_file::Locale;
+//!
+//! let de = Locale::parse("de").unwrap();
+//! let file = DesktopFileBuilder::new()
+//!     .group("Desktop Entry")
+//!     .entry("Type", "Application")
+//!     .entry("Name", "Text Editor")
+//!     .localized_entry("Name", &de, "Texteditor")
+//!     .build()
+//!     .unwrap();
+//!
+//! assert_eq!(
+//!     file.to_string(),
+//!     "[Desktop Entry]\nType=Application\nName=Text Editor\nName[de]=Texteditor\n"
+//! );
+//! ```
+use std::collections::HashSet;
+
+use thiserror::Error;
+
+use crate::{DesktopFile, Locale, OwnedDesktopFile};
+
+/// An error encountered while building a desktop file with [`GroupBuilder::build`].
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum BuildError {
+    #[error("a group must appear in one group header only (found duplicate group [{0}])")]
+    DuplicateGroup(String),
+    #[error("keys within a group must be unique (found duplicate key {0})")]
+    DuplicateKey(String),
+}
+
+/// Builds a desktop file, one group at a time. See the [module documentation](self).
+#[derive(Debug, Clone, Default)]
+pub struct DesktopFileBuilder {
+    groups: Vec<(String, Vec<(String, String)>)>,
+}
+
+impl DesktopFileBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Starts a new group named `group_name`, which is added after any previous groups.
+    pub fn group(mut self, group_name: impl Into<String>) -> GroupBuilder {
+        self.groups.push((group_name.into(), vec![]));
+        GroupBuilder { file: self }
+    }
+}
+
+/// Adds entries to the last group started with [`DesktopFileBuilder::group`].
+#[derive(Debug, Clone)]
+pub struct GroupBuilder {
+    file: DesktopFileBuilder,
+}
+
+impl GroupBuilder {
+    /// Adds an entry with the raw `value`, which should already be escaped (e.g. a newline should
+    /// be written as `\n`).
+    pub fn entry(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        let (_, entries) = self
+            .file
+            .groups
+            .last_mut()
+            .expect("a group should have been started");
+        entries.push((key.into(), value.into()));
+        self
+    }
+
+    /// Adds an entry for `key` localized in `locale` (e.g. `Name[de]`).
+    pub fn localized_entry(self, key: &str, locale: &Locale, value: impl Into<String>) -> Self {
+        self.entry(format!("{key}[{locale}]"), value)
+    }
+
+    /// Finishes this group, and starts a new group named `group_name` after it.
+    pub fn group(self, group_name: impl Into<String>) -> GroupBuilder {
+        self.file.group(group_name)
+    }
+
+    /// Builds the desktop file, which must not contain duplicate groups, or duplicate keys within a
+    /// group.
+    pub fn build(self) -> Result<OwnedDesktopFile, BuildError> {
+        let mut file = DesktopFile::default();
+        for (group_name, entries) in self.file.groups {
+            if file.group(&group_name).is_some() {
+                return Err(BuildError::DuplicateGroup(group_name));
+            }
+
+            let group = file.insert_group(group_name);
+            let mut keys = HashSet::new();
+            for (key, value) in entries {
+                if !keys.insert(key.clone()) {
+                    return Err(BuildError::DuplicateKey(key));
+                }
+                group.set_raw(key, value);
+            }
+        }
+
+        Ok(file)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use indoc::indoc;
+
+    use super::*;
+
+    #[test]
+    fn two_groups() {
+        let fr = Locale::parse("fr_CA").unwrap();
+        let file = DesktopFileBuilder::new()
+            .group("Desktop Entry")
+            .entry("Type", "Application")
+            .entry("Name", "Firefox")
+            .localized_entry("Name", &fr, "Firefox (fr_CA)")
+            .entry("Actions", "new-window;")
+            .group("Desktop Action new-window")
+            .entry("Name", "Open a New Window")
+            .entry("Exec", "firefox --new-window %u")
+            .build()
+            .unwrap();
+
+        assert_eq!(
+            file.to_string(),
+            indoc! {"
+                [Desktop Entry]
+                Type=Application
+                Name=Firefox
+                Name[fr_CA]=Firefox (fr_CA)
+                Actions=new-window;
+
+                [Desktop Action new-window]
+                Name=Open a New Window
+                Exec=firefox --new-window %u
+            "}
+        );
+        // and the output can be parsed again
+        assert!(DesktopFile::parse(&file.to_string()).is_ok());
+    }
+
+    #[test]
+    fn duplicates() {
+        let err = DesktopFileBuilder::new()
+            .group("Desktop Entry")
+            .group("Desktop Entry")
+            .build()
+            .unwrap_err();
+        assert_eq!(err, BuildError::DuplicateGroup("Desktop Entry".to_string()));
+
+        let err = DesktopFileBuilder::new()
+            .group("Desktop Entry")
+            .entry("Name", "Firefox")
+            .entry("Name", "Chromium")
+            .build()
+            .unwrap_err();
+        assert_eq!(err, BuildError::DuplicateKey("Name".to_string()));
+    }
+}
@@ -34,12 +34,74 @@
 //!     }
 //! }
 //! ```
+//!
+//! # Serde
+//! With `#[serde]` after `#[error(...)]`, the struct derives serde's traits when the calling
+//! crate's `serde` feature is enabled, with each field named after its key.
+//!
+//! # Defaults
+//! A field of type `WithDefault<T>` is a `T`, which is the default given with `#[default(...)]` (or
+//! `T::default()`, if there isn't one) when the key is missing. `#[default(...)]` must come after
+//! `#[key(...)]`, if both are present.
+//! ```
+//! use desktop_file::define_group::preamble::*;
+//! use desktop_file::{DesktopFile, ParseError};
+//!
+//! define_group! {
+//!     #[derive(Debug)]
+//!     #[error(MyError)]
+//!     pub struct MyGroup {
+//!         #[default(true)]
+//!         pub enabled: WithDefault<bool>,
+//!         #[key("Is-Fancy")]
+//!         #[default(true)]
+//!         pub fancy: WithDefault<bool>,
+//!         pub tags: WithDefault<Vec<String>>,
+//!     }
+//! }
+//!
+//! # #[derive(Debug)]
+//! # enum MyError {
+//! #     Parse(ParseError),
+//! #     RequiredKeyMissing(&'static str),
+//! # }
+//! #
+//! # impl From<ParseError> for MyError {
+//! #     fn from(value: ParseError) -> Self {
+//! #         Self::Parse(value)
+//! #     }
+//! # }
+//! #
+//! # impl From<RequiredKeyMissing> for MyError {
+//! #     fn from(value: RequiredKeyMissing) -> Self {
+//! #         Self::RequiredKeyMissing(value.0)
+//! #     }
+//! # }
+//! #
+//! let file = DesktopFile::parse("[Missing]\n[Present]\nEnabled=false\nTags=a;b\n").unwrap();
+//!
+//! // missing keys use the default
+//! let missing = MyGroup::try_from_group(file.group("Missing").unwrap()).unwrap();
+//! assert!(missing.enabled);
+//! assert!(missing.fancy);
+//! assert!(missing.tags.is_empty());
+//!
+//! // but the parsed value is used if the key is present
+//! let present = MyGroup::try_from_group(file.group("Present").unwrap()).unwrap();
+//! assert!(!present.enabled);
+//! assert!(present.fancy);
+//! assert_eq!(present.tags, ["a", "b"]);
+//! ```
+//!
+//! # Invalid values
+//! A field of type `IgnoreInvalid<T>` is an `Option<T>`, which is [`None`] if the key is missing
+//! or its value isn't a valid `T`, rather than failing to read the group.
 use std::marker::PhantomData;
 
 use crate::{FromRaw, Group, ParseError};
 
 pub mod preamble {
-    pub use super::{GroupExt, Required, RequiredKeyMissing};
+    pub use super::{GroupExt, IgnoreInvalid, Required, RequiredKeyMissing, WithDefault};
     pub use crate::define_group;
 }
 
@@ -99,6 +161,50 @@ impl<E: From<RequiredKeyMissing> + From<ParseError>, V: FromRaw> GroupValue<E> f
     }
 }
 
+pub struct IgnoreInvalid<T>(PhantomData<T>);
+
+impl<E: From<RequiredKeyMissing> + From<ParseError>, V: FromRaw> GroupValue<E>
+    for IgnoreInvalid<V>
+{
+    type Value = Option<V>;
+
+    fn get_from(group: &Group, key: &'static str) -> Result<Self::Value, E> {
+        Ok(group.get(key).and_then(Result::ok))
+    }
+}
+
+pub struct WithDefault<T>(PhantomData<T>);
+
+impl<V: FromRaw> WithDefault<V> {
+    pub fn get_from_or<E: From<RequiredKeyMissing> + From<ParseError>>(
+        group: &Group,
+        key: &'static str,
+        default: V,
+    ) -> Result<V, E> {
+        Ok(group.get_or(key, default)?)
+    }
+}
+
+impl<E: From<RequiredKeyMissing> + From<ParseError>, V: FromRaw + Default> GroupValue<E>
+    for WithDefault<V>
+{
+    type Value = V;
+
+    fn get_from(group: &Group, key: &'static str) -> Result<Self::Value, E> {
+        Self::get_from_or(group, key, V::default())
+    }
+}
+
+#[macro_export]
+macro_rules! define_group_value {
+    ($E:ty, $field_type:ty, $group:expr, $key:expr) => {
+        <$field_type as GroupValue<$E>>::get_from($group, $key)
+    };
+    ($E:ty, $field_type:ty, $group:expr, $key:expr, $default:expr) => {
+        <$field_type>::get_from_or::<$E>($group, $key, $default)
+    };
+}
+
 #[macro_export]
 macro_rules! define_group_key {
     ($name:ident) => {
@@ -112,36 +218,96 @@ macro_rules! define_group_key {
 #[macro_export]
 macro_rules! define_group {
     {
-        $(#[$meta:meta])?
-        #[error($E:ty)]
-        $vis:vis struct $name:ident {
+        @impl $E:ty, $name:ident {
             $(
                 $(#[key($field_key:expr)])?
+                $(#[default($field_default:expr)])?
                 pub $field_name:ident: $field_type:ty
-            ),*$(,)?
+            ),*
         }
     } => {
-        $(#[$meta])?
-        $vis struct $name {
-            $(pub $field_name: <$field_type as $crate::define_group::GroupValue<$E>>::Value),*
-        }
-
         impl $name {
             fn try_from_group(group: &$crate::Group) -> Result<Self, $E> {
+                // unused if every field has an explicit key
+                #[allow(unused_imports)]
                 use const_format::{map_ascii_case, Case};
 
                 use $crate::define_group::GroupValue;
-                use $crate::define_group_key;
+                use $crate::{define_group_key, define_group_value};
 
                 Ok(Self {
                     $(
-                        $field_name: <$field_type as GroupValue<$E>>::get_from(
+                        $field_name: define_group_value!(
+                            $E,
+                            $field_type,
                             group,
                             define_group_key!($(#[key($field_key)])? $field_name)
+                            $(, $field_default)?
                         )?
                     ),*
                 })
             }
         }
-    }
+    };
+    {
+        $(#[$meta:meta])?
+        #[error($E:ty)]
+        #[serde]
+        $vis:vis struct $name:ident {
+            $(
+                $(#[key($field_key:expr)])?
+                $(#[default($field_default:expr)])?
+                pub $field_name:ident: $field_type:ty
+            ),*$(,)?
+        }
+    } => {
+        $(#[$meta])?
+        #[cfg_attr(
+            feature = "serde",
+            derive(serde::Serialize, serde::Deserialize),
+            serde(rename_all = "PascalCase")
+        )]
+        $vis struct $name {
+            $(
+                $(#[cfg_attr(feature = "serde", serde(rename = $field_key))])?
+                pub $field_name: <$field_type as $crate::define_group::GroupValue<$E>>::Value
+            ),*
+        }
+
+        $crate::define_group! {
+            @impl $E, $name {
+                $(
+                    $(#[key($field_key)])?
+                    $(#[default($field_default)])?
+                    pub $field_name: $field_type
+                ),*
+            }
+        }
+    };
+    {
+        $(#[$meta:meta])?
+        #[error($E:ty)]
+        $vis:vis struct $name:ident {
+            $(
+                $(#[key($field_key:expr)])?
+                $(#[default($field_default:expr)])?
+                pub $field_name:ident: $field_type:ty
+            ),*$(,)?
+        }
+    } => {
+        $(#[$meta])?
+        $vis struct $name {
+            $(pub $field_name: <$field_type as $crate::define_group::GroupValue<$E>>::Value),*
+        }
+
+        $crate::define_group! {
+            @impl $E, $name {
+                $(
+                    $(#[key($field_key)])?
+                    $(#[default($field_default)])?
+                    pub $field_name: $field_type
+                ),*
+            }
+        }
+    };
 }
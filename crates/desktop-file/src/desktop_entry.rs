@@ -1,16 +1,45 @@
+use std::borrow::Cow;
+use std::fmt;
+use std::path::{Path, PathBuf};
+
 use thiserror::Error;
 
 use crate::define_group::preamble::*;
-use crate::{DesktopFile, FromRaw, Group, ParseError};
+use crate::{AmbiguousGroup, DesktopFile, FromRaw, Group, Locale, ParseError};
 
 #[derive(Error, Debug)]
 pub enum DesktopEntryError {
     #[error("parsing should succeed")]
     Parse(#[from] ParseError),
-    #[error("desktop entry files must contain the [Desktop Entry] group")]
-    DesktopEntryGroupMissing,
+    #[error("desktop entry files must contain the [{0}] group")]
+    DesktopEntryGroupMissing(String),
+    #[error("the desktop entry group is ambiguous - {0}")]
+    AmbiguousGroup(#[from] AmbiguousGroup),
     #[error("desktop entry files require the {0} key to be present")]
     RequiredKeyMissing(&'static str),
+    #[error("desktop entry files must contain the [Desktop Action {0}] group listed in Actions")]
+    ActionGroupMissing(String),
+}
+
+/// A problem found by [`DesktopEntry::validate`] which doesn't prevent the entry from being read,
+/// but which the specification doesn't allow.
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum ValidationWarning {
+    #[error("the {0} key is required for this type of desktop entry")]
+    RequiredKeyMissing(&'static str),
+    #[error("the {0} and {1} keys must not both be present")]
+    ConflictingKeys(&'static str, &'static str),
+    #[error("the {0} key must not be empty")]
+    EmptyValue(&'static str),
+    #[error("the {0} key is deprecated")]
+    DeprecatedKey(&'static str),
+    #[error("the {0} key has an invalid value")]
+    InvalidValue(&'static str),
+    #[error(
+        "version {0} of the specification is newer than the supported {}",
+        SpecVersion::SUPPORTED
+    )]
+    UnsupportedVersion(SpecVersion),
 }
 
 impl From<RequiredKeyMissing> for DesktopEntryError {
@@ -22,14 +51,17 @@ impl From<RequiredKeyMissing> for DesktopEntryError {
 define_group! {
     #[derive(Debug, Clone)]
     #[error(DesktopEntryError)]
+    #[serde]
     pub struct DesktopEntryCommon {
-        pub version: Option<String>,
+        pub version: IgnoreInvalid<SpecVersion>,
         pub name: Required<String>,
         pub generic_name: Option<String>,
-        pub no_display: Option<bool>,
+        #[default(false)]
+        pub no_display: WithDefault<bool>,
         pub comment: Option<String>,
         pub icon: Option<String>,
-        pub hidden: Option<bool>,
+        #[default(false)]
+        pub hidden: WithDefault<bool>,
         pub only_show_in: Option<Vec<String>>,
         pub not_show_in: Option<Vec<String>>,
     }
@@ -38,11 +70,13 @@ define_group! {
 define_group! {
     #[derive(Debug, Clone)]
     #[error(DesktopEntryError)]
+    #[serde]
     pub struct DesktopEntryApplication {
         pub try_exec: Option<String>,
         pub exec: Option<Exec>,
-        pub path: Option<String>,
-        pub terminal: Option<bool>,
+        pub path: Option<PathBuf>,
+        #[default(false)]
+        pub terminal: WithDefault<bool>,
         pub actions: Option<Vec<String>>,
         pub mime_type: Option<Vec<String>>,
         pub categories: Option<Vec<String>>,
@@ -53,6 +87,207 @@ define_group! {
         #[key("PrefersNonDefaultGPU")]
         pub prefers_non_default_gpu: Option<bool>,
         pub single_main_window: Option<bool>,
+        #[key("DBusActivatable")]
+        #[default(false)]
+        pub dbus_activatable: WithDefault<bool>,
+    }
+}
+
+/// The main, additional and reserved categories registered in the [Desktop Menu
+/// Specification](https://specifications.freedesktop.org/menu-spec/latest/apa.html).
+const REGISTERED_CATEGORIES: &[&str] = &[
+    // main categories
+    "AudioVideo",
+    "Audio",
+    "Video",
+    "Development",
+    "Education",
+    "Game",
+    "Graphics",
+    "Network",
+    "Office",
+    "Science",
+    "Settings",
+    "System",
+    "Utility",
+    // additional categories
+    "Building",
+    "Debugger",
+    "IDE",
+    "GUIDesigner",
+    "Profiling",
+    "RevisionControl",
+    "Translation",
+    "Calendar",
+    "ContactManagement",
+    "Database",
+    "Dictionary",
+    "Chart",
+    "Email",
+    "Finance",
+    "FlowChart",
+    "PDA",
+    "ProjectManagement",
+    "Presentation",
+    "Spreadsheet",
+    "WordProcessor",
+    "2DGraphics",
+    "VectorGraphics",
+    "RasterGraphics",
+    "3DGraphics",
+    "Scanning",
+    "OCR",
+    "Photography",
+    "Publishing",
+    "Viewer",
+    "TextTools",
+    "DesktopSettings",
+    "HardwareSettings",
+    "Printing",
+    "PackageManager",
+    "Dialup",
+    "InstantMessaging",
+    "Chat",
+    "IRCClient",
+    "Feed",
+    "FileTransfer",
+    "HamRadio",
+    "News",
+    "P2P",
+    "RemoteAccess",
+    "Telephony",
+    "TelephonyTools",
+    "VideoConference",
+    "WebBrowser",
+    "WebDevelopment",
+    "Midi",
+    "Mixer",
+    "Sequencer",
+    "Tuner",
+    "TV",
+    "AudioVideoEditing",
+    "Player",
+    "Recorder",
+    "DiscBurning",
+    "ActionGame",
+    "AdventureGame",
+    "ArcadeGame",
+    "BoardGame",
+    "BlocksGame",
+    "CardGame",
+    "KidsGame",
+    "LogicGame",
+    "RolePlaying",
+    "Shooter",
+    "Simulation",
+    "SportsGame",
+    "StrategyGame",
+    "Art",
+    "Construction",
+    "Music",
+    "Languages",
+    "ArtificialIntelligence",
+    "Astronomy",
+    "Biology",
+    "Chemistry",
+    "ComputerScience",
+    "DataVisualization",
+    "Economy",
+    "Electricity",
+    "Geography",
+    "Geology",
+    "Geoscience",
+    "History",
+    "Humanities",
+    "ImageProcessing",
+    "Literature",
+    "Maps",
+    "Math",
+    "NumericalAnalysis",
+    "MedicalSoftware",
+    "Physics",
+    "Robotics",
+    "Spirituality",
+    "Sports",
+    "ParallelComputing",
+    "Amusement",
+    "Archiving",
+    "Compression",
+    "Electronics",
+    "Emulator",
+    "Engineering",
+    "FileTools",
+    "FileManager",
+    "TerminalEmulator",
+    "Filesystem",
+    "Monitor",
+    "Security",
+    "Accessibility",
+    "Calculator",
+    "Clock",
+    "TextEditor",
+    "Documentation",
+    "Adult",
+    "Core",
+    "KDE",
+    "GNOME",
+    "XFCE",
+    "DDE",
+    "GTK",
+    "Qt",
+    "Motif",
+    "Java",
+    "ConsoleOnly",
+    // reserved categories
+    "Screensaver",
+    "TrayIcon",
+    "Applet",
+    "Shell",
+];
+
+impl DesktopEntryApplication {
+    /// Splits the `Categories` into those which are registered (or are extensions, beginning with
+    /// `X-`) and those which aren't, in the order they're listed.
+    pub fn categories_validated(&self) -> (Vec<String>, Vec<String>) {
+        self.categories
+            .iter()
+            .flatten()
+            .filter(|category| !category.is_empty())
+            .cloned()
+            .partition(|category| {
+                category.starts_with("X-") || REGISTERED_CATEGORIES.contains(&category.as_str())
+            })
+    }
+}
+
+define_group! {
+    #[derive(Debug, Clone)]
+    #[error(DesktopEntryError)]
+    #[serde]
+    pub struct DesktopEntryLink {
+        #[key("URL")]
+        pub url: Required<String>,
+    }
+}
+
+impl DesktopEntryLink {
+    /// Parses the `URL`, which is kept as-is in [`DesktopEntryLink::url`].
+    pub fn parsed_url(&self) -> Result<Url, ParseError> {
+        Url::parse(&self.url)
+    }
+}
+
+/// Directory entries have no keys beyond the common keys.
+#[derive(Debug, Clone)]
+pub struct DesktopEntryDirectory {}
+
+define_group! {
+    #[derive(Debug, Clone)]
+    #[error(DesktopEntryError)]
+    pub struct DesktopAction {
+        pub name: Required<String>,
+        pub icon: Option<String>,
+        pub exec: Option<Exec>,
     }
 }
 
@@ -60,6 +295,8 @@ define_group! {
 pub enum DesktopEntryType {
     Unknown,
     Application(DesktopEntryApplication),
+    Link(DesktopEntryLink),
+    Directory(DesktopEntryDirectory),
 }
 
 impl DesktopEntryType {
@@ -68,6 +305,8 @@ impl DesktopEntryType {
             "Application" => Ok(Self::Application(DesktopEntryApplication::try_from_group(
                 group,
             )?)),
+            "Link" => Ok(Self::Link(DesktopEntryLink::try_from_group(group)?)),
+            "Directory" => Ok(Self::Directory(DesktopEntryDirectory {})),
             _ => Ok(Self::Unknown),
         }
     }
@@ -75,6 +314,7 @@ impl DesktopEntryType {
 
 #[derive(Debug, Clone)]
 pub struct DesktopEntry<'file, 'input> {
+    pub file: &'file DesktopFile<'input>,
     pub group: &'file Group<'input>,
     pub common: DesktopEntryCommon,
     pub for_type: DesktopEntryType,
@@ -82,49 +322,288 @@ pub struct DesktopEntry<'file, 'input> {
 
 impl<'file: 'input, 'input> DesktopEntry<'file, 'input> {
     pub fn try_from_file(file: &'file DesktopFile<'input>) -> Result<Self, DesktopEntryError> {
+        Self::try_from_named_group(file, "Desktop Entry")
+    }
+
+    /// Like [`DesktopEntry::try_from_file`], but reads the entry from the group named `group_name`
+    /// rather than `Desktop Entry`, for files which use the same keys in a differently named group.
+    pub fn try_from_named_group(
+        file: &'file DesktopFile<'input>,
+        group_name: &str,
+    ) -> Result<Self, DesktopEntryError> {
         let group = file
-            .group("Desktop Entry")
-            .ok_or(DesktopEntryError::DesktopEntryGroupMissing)?;
+            .group(group_name)
+            .ok_or_else(|| DesktopEntryError::DesktopEntryGroupMissing(group_name.to_string()))?;
+
+        Self::try_from_group(file, group)
+    }
 
+    /// Like [`DesktopEntry::try_from_file`], but accepts a `Desktop Entry` group with any case
+    /// (e.g. `[desktop entry]`), as long as only one group matches.
+    pub fn try_from_file_ignore_case(
+        file: &'file DesktopFile<'input>,
+    ) -> Result<Self, DesktopEntryError> {
+        let group = file.group_ignore_case("Desktop Entry")?.ok_or_else(|| {
+            DesktopEntryError::DesktopEntryGroupMissing("Desktop Entry".to_string())
+        })?;
+
+        Self::try_from_group(file, group)
+    }
+
+    fn try_from_group(
+        file: &'file DesktopFile<'input>,
+        group: &'file Group<'input>,
+    ) -> Result<Self, DesktopEntryError> {
         let ty: String = group.get_required::<_, DesktopEntryError>("Type")?;
         let common = DesktopEntryCommon::try_from_group(group)?;
         let for_type = DesktopEntryType::try_from_group(&ty, group)?;
 
         Ok(Self {
+            file,
             group,
             common,
             for_type,
         })
     }
+
+    /// Checks the entry for consistency problems which aren't caught when it's read, returning a
+    /// warning for each problem found.
+    pub fn validate(&self) -> Vec<ValidationWarning> {
+        let mut warnings = vec![];
+
+        let dbus_activatable = match &self.for_type {
+            DesktopEntryType::Application(app) => app.dbus_activatable,
+            _ => false,
+        };
+        match self.group.get_raw("Type") {
+            Some("Application") if self.group.get_raw("Exec").is_none() && !dbus_activatable => {
+                warnings.push(ValidationWarning::RequiredKeyMissing("Exec"));
+            }
+            Some("Link") if self.group.get_raw("URL").is_none() => {
+                warnings.push(ValidationWarning::RequiredKeyMissing("URL"));
+            }
+            _ => {}
+        }
+
+        if self.group.get_raw("OnlyShowIn").is_some() && self.group.get_raw("NotShowIn").is_some() {
+            warnings.push(ValidationWarning::ConflictingKeys(
+                "OnlyShowIn",
+                "NotShowIn",
+            ));
+        }
+
+        for key in ["TryExec", "Icon"] {
+            if self.group.get_raw(key) == Some("") {
+                warnings.push(ValidationWarning::EmptyValue(key));
+            }
+        }
+
+        if self.group.contains_key("Encoding") {
+            warnings.push(ValidationWarning::DeprecatedKey("Encoding"));
+        }
+
+        match self.group.get::<SpecVersion>("Version") {
+            Some(Ok(version)) if version > SpecVersion::SUPPORTED => {
+                warnings.push(ValidationWarning::UnsupportedVersion(version));
+            }
+            Some(Err(_)) => warnings.push(ValidationWarning::InvalidValue("Version")),
+            _ => {}
+        }
+
+        warnings
+    }
+
+    /// Reads the `Keywords` localized for `locale`, without falling back to the default
+    /// (unlocalized) `Keywords`. The default keywords are available in
+    /// [`DesktopEntryApplication::keywords`].
+    pub fn localized_keywords(&self, locale: &Locale) -> Result<Option<Vec<String>>, ParseError> {
+        self.localized_raw("Keywords", locale)
+            .map(Vec::<String>::from_raw)
+            .transpose()
+    }
+
+    /// The `Name` to display in `locale`, falling back to less specific locales and then the
+    /// default [`DesktopEntryCommon::name`]. A localized value which can't be parsed is skipped.
+    pub fn name(&self, locale: Option<&Locale>) -> Cow<'_, str> {
+        self.localized_string("Name", locale)
+            .map(Cow::Owned)
+            .unwrap_or(Cow::Borrowed(&self.common.name))
+    }
+
+    /// The `GenericName` to display in `locale`, as for [`DesktopEntry::name`].
+    pub fn generic_name(&self, locale: Option<&Locale>) -> Option<Cow<'_, str>> {
+        self.localized_string("GenericName", locale)
+            .map(Cow::Owned)
+            .or(self.common.generic_name.as_deref().map(Cow::Borrowed))
+    }
+
+    /// The `Comment` to display in `locale`, as for [`DesktopEntry::name`].
+    pub fn comment(&self, locale: Option<&Locale>) -> Option<Cow<'_, str>> {
+        self.localized_string("Comment", locale)
+            .map(Cow::Owned)
+            .or(self.common.comment.as_deref().map(Cow::Borrowed))
+    }
+
+    /// Reads `key` localized for `locale` as a string, if it's localized and valid.
+    fn localized_string(&self, key: &str, locale: Option<&Locale>) -> Option<String> {
+        let value = self.localized_raw(key, locale?)?;
+        String::from_raw(value).ok()
+    }
+
+    /// Returns the raw value of `key` for the best match of `locale`, without falling back to the
+    /// default (unlocalized) value.
+    fn localized_raw(&self, key: &str, locale: &Locale) -> Option<&str> {
+        let mut matches = locale.key(key).matches();
+        // the last match is always the default value
+        matches.pop();
+
+        matches
+            .iter()
+            .find_map(|key| self.group.get_raw_ignoring_encoding(key))
+    }
+
+    /// Reads the additional application actions listed in the `Actions` key from their
+    /// `[Desktop Action <action>]` groups, in the order they are listed. Entries which aren't
+    /// applications have no actions.
+    pub fn actions(&self) -> Result<Vec<DesktopAction>, DesktopEntryError> {
+        let actions = match &self.for_type {
+            DesktopEntryType::Application(app) => app.actions.as_deref().unwrap_or_default(),
+            _ => &[],
+        };
+
+        actions
+            .iter()
+            .filter(|action| !action.is_empty())
+            .map(|action| {
+                let group = self
+                    .file
+                    .group(&format!("Desktop Action {action}"))
+                    .ok_or_else(|| DesktopEntryError::ActionGroupMissing(action.clone()))?;
+
+                DesktopAction::try_from_group(group)
+            })
+            .collect()
+    }
+}
+
+/// The version of the specification an entry conforms to, from its `Version` key (e.g. `1.5`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SpecVersion {
+    pub major: u32,
+    pub minor: u32,
+}
+
+impl SpecVersion {
+    /// The newest version of the specification this crate supports.
+    pub const SUPPORTED: Self = Self { major: 1, minor: 5 };
+}
+
+impl fmt::Display for SpecVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}", self.major, self.minor)
+    }
+}
+
+peg::parser! {
+    grammar version_parser() for str {
+        rule number() -> u32 = n:$(['0'..='9']+) {? n.parse().or(Err("version number")) };
+
+        pub rule version() -> SpecVersion
+            = major:number() "." minor:number() { SpecVersion { major, minor } };
+    }
+}
+
+/// Parses values of type `string` which are versions of the specification, such as the `Version`
+/// key.
+impl FromRaw for SpecVersion {
+    fn from_raw(value: &str) -> Result<Self, ParseError> {
+        Ok(version_parser::version(&String::from_raw(value)?)?)
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum ExecArgument {
     String(String),
     FieldCode(char),
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Exec {
     pub program: String,
     pub arguments: Vec<ExecArgument>,
 }
 
+impl Exec {
+    /// Expands the field codes in the command line, producing the program followed by its
+    /// arguments.
+    ///
+    /// - `%f` and `%F` expand to the first file and all files, respectively.
+    /// - `%u` and `%U` expand to the first URL and all URLs, respectively, where `url` (if present)
+    ///   comes before any of the `files`.
+    /// - `%i` expands to the two arguments `--icon <icon>` if `icon` is present.
+    /// - `%c` expands to `app_name`.
+    /// - `%k` expands to `location`, the path of the desktop file, if present.
+    /// - All other field codes (including the deprecated codes) are removed.
+    pub fn expand(
+        &self,
+        files: &[String],
+        url: Option<&str>,
+        app_name: &str,
+        icon: Option<&str>,
+        location: Option<&Path>,
+    ) -> Vec<String> {
+        let urls = || url.into_iter().chain(files.iter().map(String::as_str));
+
+        let mut expanded = vec![self.program.clone()];
+        for argument in &self.arguments {
+            match argument {
+                ExecArgument::String(s) => expanded.push(s.clone()),
+                ExecArgument::FieldCode('f') => expanded.extend(files.first().cloned()),
+                ExecArgument::FieldCode('F') => expanded.extend(files.iter().cloned()),
+                ExecArgument::FieldCode('u') => expanded.extend(urls().next().map(String::from)),
+                ExecArgument::FieldCode('U') => expanded.extend(urls().map(String::from)),
+                ExecArgument::FieldCode('i') => {
+                    if let Some(icon) = icon {
+                        expanded.extend(["--icon".to_string(), icon.to_string()]);
+                    }
+                }
+                ExecArgument::FieldCode('c') => expanded.push(app_name.to_string()),
+                ExecArgument::FieldCode('k') => {
+                    expanded.extend(location.map(|location| location.to_string_lossy().into()))
+                }
+                ExecArgument::FieldCode(_) => {}
+            }
+        }
+
+        expanded
+    }
+}
+
 peg::parser! {
     grammar exec_parser() for str {
         rule program() -> &'input str = $([^' ']+);
 
         rule argument_field_code() -> char = "%" fc:[^' '] { fc };
 
-        // TODO: is this the correct escape behaviour?
-        rule argument_quoted_string_escape() -> char = "\\" c:[_] { c };
-        rule argument_quoted_string_char() -> char = [^'\\' | '\"'];
+        // inside a quoted argument, only `"`, `` ` ``, `$` and `\` are escaped with a backslash. as
+        // a leniency, a backslash before any other character is kept as a literal backslash rather
+        // than rejected.
+        rule argument_quoted_string_escapable() -> char = ['"' | '`' | '$' | '\\'];
+        rule argument_quoted_string_escape() -> char
+            = "\\" c:argument_quoted_string_escapable() { c };
+        rule argument_quoted_string_char() -> char
+            = [^'\\' | '"']
+            / "\\" !argument_quoted_string_escapable() { '\\' };
         rule argument_quoted_string() -> String
             = "\"" s:(argument_quoted_string_escape() / argument_quoted_string_char())* "\"" {
                 s.iter().collect::<String>()
             };
 
-        // TODO: how to handle reserved characters?
+        // the spec requires reserved characters (e.g. `$`, `*`, `;`) in an argument to be quoted,
+        // but as a leniency they're accepted unquoted
         rule argument_string() -> &'input str = $([^' ']+);
 
         rule argument() -> ExecArgument
@@ -147,6 +626,111 @@ peg::parser! {
     }
 }
 
+/// A URL, such as the `URL` of a link. The URL is kept as it was written, and is also split into
+/// its percent-decoded components.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Url {
+    raw: String,
+    components: UrlComponents,
+}
+
+/// The percent-decoded components of a [`Url`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum UrlComponents {
+    /// A `file://` URL, where `host` is empty for local files.
+    File { host: String, path: PathBuf },
+    /// An `http://` or `https://` URL, where `path` includes any query and fragment.
+    Http {
+        secure: bool,
+        host: String,
+        path: String,
+    },
+    /// A `mailto:` URL.
+    Mailto { address: String },
+    /// A URL with any other scheme, which isn't decoded.
+    Other { scheme: String, rest: String },
+}
+
+impl Url {
+    /// Parses an (unescaped) URL. Spaces are accepted as-is, rather than requiring them to be
+    /// percent-encoded, as they often aren't in desktop files. URLs without a scheme, and URLs
+    /// with a known scheme but a missing host, path or address, or an invalid percent-encoding,
+    /// are rejected.
+    pub fn parse(value: &str) -> Result<Self, ParseError> {
+        let components = url_parser::url(value)?;
+
+        Ok(Url {
+            raw: value.to_string(),
+            components,
+        })
+    }
+
+    /// The URL as it was written, without any decoding.
+    pub fn as_str(&self) -> &str {
+        &self.raw
+    }
+
+    pub fn components(&self) -> &UrlComponents {
+        &self.components
+    }
+}
+
+peg::parser! {
+    grammar url_parser() for str {
+        rule hex_digit() = ['0'..='9' | 'a'..='f' | 'A'..='F'];
+        rule percent_encoded() -> u8
+            = "%" h:$(hex_digit()*<2>) { u8::from_str_radix(h, 16).unwrap() };
+        rule decoded_bytes() -> Vec<u8>
+            = b:percent_encoded() { vec![b] }
+            / c:[^'%'] { c.to_string().into_bytes() };
+        rule decoded() -> String
+            = b:decoded_bytes()* {?
+                String::from_utf8(b.concat()).or(Err("percent-encoded UTF-8"))
+            };
+
+        rule scheme() -> &'input str
+            = $(['a'..='z' | 'A'..='Z'] ['a'..='z' | 'A'..='Z' | '0'..='9' | '+' | '-' | '.']*);
+        rule host() -> &'input str = $([^'/' | '?' | '#' | ' ']+);
+
+        rule file() -> UrlComponents
+            = "file://" host:$([^'/']*) path:decoded() {?
+                if path.is_empty() {
+                    return Err("path");
+                }
+                let host = host.to_string();
+                Ok(UrlComponents::File { host, path: path.into() })
+            };
+        rule http() -> UrlComponents
+            = "http" secure:"s"? "://" host:host() path:decoded() {
+                let (secure, host) = (secure.is_some(), host.to_string());
+                UrlComponents::Http { secure, host, path }
+            };
+        rule mailto() -> UrlComponents
+            = "mailto:" address:decoded() {?
+                if address.is_empty() {
+                    return Err("address");
+                }
+                Ok(UrlComponents::Mailto { address })
+            };
+        // known schemes which don't parse are malformed, rather than falling back to `other`
+        rule known_scheme() = ("file" / "https" / "http" / "mailto") ":";
+        rule other() -> UrlComponents
+            = !known_scheme() scheme:scheme() ":" rest:$([_]+) {
+                let (scheme, rest) = (scheme.to_string(), rest.to_string());
+                UrlComponents::Other { scheme, rest }
+            };
+
+        pub rule url() -> UrlComponents = file() / http() / mailto() / other();
+    }
+}
+
+/// Parses values of type `string` which are URLs, such as the `URL` key.
+impl FromRaw for Url {
+    fn from_raw(value: &str) -> Result<Self, ParseError> {
+        Url::parse(&String::from_raw(value)?)
+    }
+}
+
 impl FromRaw for Exec {
     fn from_raw(value: &str) -> Result<Self, crate::ParseError> {
         let value = String::from_raw(value)?;
@@ -158,8 +742,542 @@ impl FromRaw for Exec {
 
 #[cfg(test)]
 mod tests {
+    use indoc::indoc;
+
     use super::*;
 
+    #[test]
+    fn localized_strings() {
+        let file = DesktopFile::parse(indoc! {"
+            [Desktop Entry]
+            Type=Application
+            Name=Text Editor
+            Name[de]=Texteditor
+            Comment=Edit\\stext
+            Comment[de]=Text\\sbearbeiten
+        "})
+        .unwrap();
+        let desktop_entry = DesktopEntry::try_from_file(&file).unwrap();
+
+        let de_at = Locale::parse("de_AT").unwrap();
+        assert_eq!(desktop_entry.name(Some(&de_at)), "Texteditor");
+        // localized values are unescaped too
+        assert_eq!(
+            desktop_entry.comment(Some(&de_at)).as_deref(),
+            Some("Text bearbeiten")
+        );
+        assert_eq!(desktop_entry.generic_name(Some(&de_at)), None);
+
+        // unmatched locales, or no locale, fall back to the default values
+        let fr = Locale::parse("fr").unwrap();
+        assert_eq!(desktop_entry.name(Some(&fr)), "Text Editor");
+        assert_eq!(
+            desktop_entry.comment(Some(&fr)).as_deref(),
+            Some("Edit text")
+        );
+        assert_eq!(desktop_entry.name(None), "Text Editor");
+    }
+
+    #[test]
+    fn terminal_default() {
+        let terminal = |contents: &str| {
+            let file = DesktopFile::parse(contents).unwrap();
+            match DesktopEntry::try_from_file(&file).unwrap().for_type {
+                DesktopEntryType::Application(app) => app.terminal,
+                _ => unreachable!(),
+            }
+        };
+
+        // applications don't run in a terminal unless they say so
+        assert!(!terminal(
+            "[Desktop Entry]\nType=Application\nName=A\nExec=a\n"
+        ));
+        assert!(terminal(
+            "[Desktop Entry]\nType=Application\nName=A\nExec=a\nTerminal=true\n"
+        ));
+    }
+
+    #[test]
+    fn localized_strings_with_encoding() {
+        let file = DesktopFile::parse(indoc! {"
+            [Desktop Entry]
+            Type=Application
+            Name=Text Editor
+            Name[de_DE.UTF-8]=Texteditor
+            GenericName=Editor
+            GenericName[de.UTF-8@euro]=Bearbeiter
+        "})
+        .unwrap();
+        let desktop_entry = DesktopEntry::try_from_file(&file).unwrap();
+
+        // the encoding in the file is ignored, as for Group::get_raw
+        let de_de = Locale::parse("de_DE").unwrap();
+        assert_eq!(
+            file.group("Desktop Entry")
+                .unwrap()
+                .get_raw(de_de.key("Name")),
+            Some("Texteditor")
+        );
+        assert_eq!(desktop_entry.name(Some(&de_de)), "Texteditor");
+        let de_euro = Locale::parse("de_DE@euro").unwrap();
+        assert_eq!(
+            desktop_entry.generic_name(Some(&de_euro)).as_deref(),
+            Some("Bearbeiter")
+        );
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serialize() {
+        let file = DesktopFile::parse(indoc! {"
+            [Desktop Entry]
+            Type=Application
+            Name=Firefox
+            OnlyShowIn=GNOME;
+            Exec=firefox %u
+            DBusActivatable=false
+        "})
+        .unwrap();
+        let desktop_entry = DesktopEntry::try_from_file(&file).unwrap();
+
+        // keys are named as in the file
+        let common = serde_json::to_value(&desktop_entry.common).unwrap();
+        assert_eq!(common["Name"], "Firefox");
+        assert_eq!(common["OnlyShowIn"], serde_json::json!(["GNOME"]));
+        assert_eq!(common["NoDisplay"], false);
+
+        let DesktopEntryType::Application(app) = desktop_entry.for_type else {
+            panic!("expected an application");
+        };
+        let json = serde_json::to_value(&app).unwrap();
+        assert_eq!(json["DBusActivatable"], false);
+        assert_eq!(
+            json["Exec"],
+            serde_json::json!({
+                "program": "firefox",
+                "arguments": [{ "FieldCode": "u" }],
+            })
+        );
+
+        // and read back
+        let app: DesktopEntryApplication = serde_json::from_value(json).unwrap();
+        assert_eq!(app.exec, Exec::from_raw("firefox %u").ok());
+    }
+
+    #[test]
+    fn localized_keywords() {
+        let file = DesktopFile::parse(indoc! {"
+            [Desktop Entry]
+            Type=Application
+            Name=Text Editor
+            Keywords=text;editor;
+            Keywords[de]=Text;Editor;
+        "})
+        .unwrap();
+        let desktop_entry = DesktopEntry::try_from_file(&file).unwrap();
+
+        let de_at = Locale::parse("de_AT").unwrap();
+        assert_eq!(
+            desktop_entry.localized_keywords(&de_at).unwrap(),
+            Some(vec!["Text".to_string(), "Editor".to_string()])
+        );
+
+        // no fallback to the default keywords
+        let fr = Locale::parse("fr").unwrap();
+        assert_eq!(desktop_entry.localized_keywords(&fr).unwrap(), None);
+
+        let DesktopEntryType::Application(app) = desktop_entry.for_type else {
+            panic!("expected an application");
+        };
+        assert_eq!(
+            app.keywords,
+            Some(vec!["text".to_string(), "editor".to_string()])
+        );
+    }
+
+    #[test]
+    fn actions() {
+        let file = DesktopFile::parse(indoc! {"
+            [Desktop Entry]
+            Type=Application
+            Name=Firefox
+            Exec=firefox %u
+            Actions=new-window;new-private-window;
+
+            [Desktop Action new-window]
+            Name=Open a New Window
+            Exec=firefox --new-window %u
+
+            [Desktop Action new-private-window]
+            Name=Open a New Private Window
+            Icon=private-browsing
+            Exec=firefox --private-window %u
+        "})
+        .unwrap();
+        let desktop_entry = DesktopEntry::try_from_file(&file).unwrap();
+
+        let actions = desktop_entry.actions().unwrap();
+        assert_eq!(actions.len(), 2);
+
+        assert_eq!(actions[0].name, "Open a New Window");
+        assert_eq!(actions[0].icon, None);
+        assert_eq!(
+            actions[0].exec,
+            Some(Exec::from_raw("firefox --new-window %u").unwrap())
+        );
+
+        assert_eq!(actions[1].name, "Open a New Private Window");
+        assert_eq!(actions[1].icon.as_deref(), Some("private-browsing"));
+        assert_eq!(
+            actions[1].exec,
+            Some(Exec::from_raw("firefox --private-window %u").unwrap())
+        );
+    }
+
+    #[test]
+    fn actions_group_missing() {
+        let file = DesktopFile::parse(indoc! {"
+            [Desktop Entry]
+            Type=Application
+            Name=Firefox
+            Actions=new-window;
+
+            [Desktop Action new-private-window]
+            Name=Open a New Private Window
+        "})
+        .unwrap();
+        let desktop_entry = DesktopEntry::try_from_file(&file).unwrap();
+
+        let err = desktop_entry.actions().unwrap_err();
+        assert!(
+            matches!(err, DesktopEntryError::ActionGroupMissing(action) if action == "new-window")
+        );
+    }
+
+    #[test]
+    fn named_group() {
+        let file = DesktopFile::parse(indoc! {"
+            [Service]
+            Type=Application
+            Name=Indexer
+            Exec=indexer --daemon
+        "})
+        .unwrap();
+
+        let desktop_entry = DesktopEntry::try_from_named_group(&file, "Service").unwrap();
+        assert_eq!(desktop_entry.common.name, "Indexer");
+        assert!(matches!(
+            desktop_entry.for_type,
+            DesktopEntryType::Application(_)
+        ));
+
+        // the error names the missing group
+        let err = DesktopEntry::try_from_file(&file).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "desktop entry files must contain the [Desktop Entry] group"
+        );
+    }
+
+    #[test]
+    fn ignore_case() {
+        let file = DesktopFile::parse(indoc! {"
+            [desktop entry]
+            Type=Application
+            Name=Firefox
+        "})
+        .unwrap();
+
+        let desktop_entry = DesktopEntry::try_from_file_ignore_case(&file).unwrap();
+        assert_eq!(desktop_entry.common.name, "Firefox");
+        // only if asked to
+        assert!(DesktopEntry::try_from_file(&file).is_err());
+
+        let file = DesktopFile::parse(indoc! {"
+            [Desktop Entry]
+            Type=Application
+            Name=Firefox
+
+            [DESKTOP ENTRY]
+            Type=Application
+            Name=Chromium
+        "})
+        .unwrap();
+        let err = DesktopEntry::try_from_file_ignore_case(&file).unwrap_err();
+        assert!(matches!(err, DesktopEntryError::AmbiguousGroup(_)));
+    }
+
+    #[test]
+    fn link() {
+        let file = DesktopFile::parse(indoc! {"
+            [Desktop Entry]
+            Type=Link
+            Name=Rust
+            URL=https://www.rust-lang.org/
+        "})
+        .unwrap();
+        let desktop_entry = DesktopEntry::try_from_file(&file).unwrap();
+
+        let DesktopEntryType::Link(link) = desktop_entry.for_type else {
+            panic!("expected a link");
+        };
+        assert_eq!(link.url, "https://www.rust-lang.org/");
+    }
+
+    #[test]
+    fn link_url_spaced() {
+        let file = DesktopFile::parse(indoc! {"
+            [Desktop Entry]
+            Type=Link
+            Name=Notes
+            URL=file:///home/user/My Documents/notes.txt
+        "})
+        .unwrap();
+        let desktop_entry = DesktopEntry::try_from_file(&file).unwrap();
+
+        let DesktopEntryType::Link(link) = desktop_entry.for_type else {
+            panic!("expected a link");
+        };
+        let url = link.parsed_url().unwrap();
+        assert_eq!(url.as_str(), "file:///home/user/My Documents/notes.txt");
+        assert_eq!(
+            url.components(),
+            &UrlComponents::File {
+                host: "".to_string(),
+                path: "/home/user/My Documents/notes.txt".into(),
+            }
+        );
+    }
+
+    #[test]
+    fn url_encoded() {
+        let url = Url::from_raw("https://example.com/caf%C3%A9%20menu?q=a%2Bb").unwrap();
+        assert_eq!(url.as_str(), "https://example.com/caf%C3%A9%20menu?q=a%2Bb");
+        assert_eq!(
+            url.components(),
+            &UrlComponents::Http {
+                secure: true,
+                host: "example.com".to_string(),
+                path: "/café menu?q=a+b".to_string(),
+            }
+        );
+
+        let url = Url::from_raw("mailto:someone%40example.com").unwrap();
+        assert_eq!(
+            url.components(),
+            &UrlComponents::Mailto {
+                address: "someone@example.com".to_string()
+            }
+        );
+
+        // other schemes are kept as-is
+        let url = Url::from_raw("ftp://example.com/a%20b").unwrap();
+        assert_eq!(
+            url.components(),
+            &UrlComponents::Other {
+                scheme: "ftp".to_string(),
+                rest: "//example.com/a%20b".to_string(),
+            }
+        );
+    }
+
+    #[test]
+    fn url_malformed() {
+        // no scheme
+        assert!(Url::from_raw("www.example.com").is_err());
+        // no host, path or address
+        assert!(Url::from_raw("http:///index.html").is_err());
+        assert!(Url::from_raw("file://").is_err());
+        assert!(Url::from_raw("mailto:").is_err());
+        // bad percent-encodings
+        assert!(Url::from_raw("https://example.com/%zz").is_err());
+        assert!(Url::from_raw("https://example.com/%C3").is_err());
+    }
+
+    #[test]
+    fn link_url_missing() {
+        let file = DesktopFile::parse(indoc! {"
+            [Desktop Entry]
+            Type=Link
+            Name=Rust
+        "})
+        .unwrap();
+
+        let err = DesktopEntry::try_from_file(&file).unwrap_err();
+        assert!(matches!(err, DesktopEntryError::RequiredKeyMissing("URL")));
+    }
+
+    #[test]
+    fn directory() {
+        let file = DesktopFile::parse(indoc! {"
+            [Desktop Entry]
+            Type=Directory
+            Name=Games
+        "})
+        .unwrap();
+        let desktop_entry = DesktopEntry::try_from_file(&file).unwrap();
+
+        assert!(matches!(
+            desktop_entry.for_type,
+            DesktopEntryType::Directory(_)
+        ));
+    }
+
+    #[test]
+    fn validate() {
+        let file = DesktopFile::parse(indoc! {"
+            [Desktop Entry]
+            Type=Application
+            Name=Firefox
+            Exec=firefox %u
+            Icon=firefox
+        "})
+        .unwrap();
+        let desktop_entry = DesktopEntry::try_from_file(&file).unwrap();
+        assert_eq!(desktop_entry.validate(), []);
+    }
+
+    #[test]
+    fn validate_both_show_in() {
+        let file = DesktopFile::parse(indoc! {"
+            [Desktop Entry]
+            Type=Application
+            Name=Firefox
+            Exec=firefox %u
+            OnlyShowIn=GNOME;
+            NotShowIn=KDE;
+        "})
+        .unwrap();
+        let desktop_entry = DesktopEntry::try_from_file(&file).unwrap();
+        assert_eq!(
+            desktop_entry.validate(),
+            [ValidationWarning::ConflictingKeys(
+                "OnlyShowIn",
+                "NotShowIn"
+            )]
+        );
+    }
+
+    #[test]
+    fn validate_exec_missing() {
+        let file = DesktopFile::parse(indoc! {"
+            [Desktop Entry]
+            Type=Application
+            Name=Firefox
+            TryExec=
+        "})
+        .unwrap();
+        let desktop_entry = DesktopEntry::try_from_file(&file).unwrap();
+        assert_eq!(
+            desktop_entry.validate(),
+            [
+                ValidationWarning::RequiredKeyMissing("Exec"),
+                ValidationWarning::EmptyValue("TryExec"),
+            ]
+        );
+
+        // D-Bus activatable applications don't need Exec
+        let file = DesktopFile::parse(indoc! {"
+            [Desktop Entry]
+            Type=Application
+            Name=Firefox
+            DBusActivatable=true
+        "})
+        .unwrap();
+        let desktop_entry = DesktopEntry::try_from_file(&file).unwrap();
+        assert_eq!(desktop_entry.validate(), []);
+    }
+
+    #[test]
+    fn validate_encoding() {
+        let file = DesktopFile::parse(indoc! {"
+            [Desktop Entry]
+            Type=Application
+            Name=Firefox
+            Exec=firefox %u
+            Encoding=UTF-8
+        "})
+        .unwrap();
+        let desktop_entry = DesktopEntry::try_from_file(&file).unwrap();
+        assert_eq!(
+            desktop_entry.validate(),
+            [ValidationWarning::DeprecatedKey("Encoding")]
+        );
+    }
+
+    #[test]
+    fn spec_version() {
+        assert_eq!(
+            SpecVersion::from_raw("1.0"),
+            Ok(SpecVersion { major: 1, minor: 0 })
+        );
+        assert_eq!(SpecVersion::from_raw("1.5"), Ok(SpecVersion::SUPPORTED));
+        assert!(SpecVersion::from_raw("abc").is_err());
+        assert!(SpecVersion::from_raw("1").is_err());
+        assert_eq!(
+            SpecVersion {
+                major: 1,
+                minor: 10
+            }
+            .to_string(),
+            "1.10"
+        );
+    }
+
+    #[test]
+    fn validate_version() {
+        let entry = |version: &str| {
+            format!("[Desktop Entry]\nType=Directory\nName=Games\nVersion={version}\n")
+        };
+
+        let contents = entry("1.5");
+        let file = DesktopFile::parse(&contents).unwrap();
+        let desktop_entry = DesktopEntry::try_from_file(&file).unwrap();
+        assert_eq!(desktop_entry.common.version, Some(SpecVersion::SUPPORTED));
+        assert_eq!(desktop_entry.validate(), []);
+
+        let contents = entry("1.6");
+        let file = DesktopFile::parse(&contents).unwrap();
+        let desktop_entry = DesktopEntry::try_from_file(&file).unwrap();
+        assert_eq!(
+            desktop_entry.validate(),
+            [ValidationWarning::UnsupportedVersion(SpecVersion {
+                major: 1,
+                minor: 6
+            })]
+        );
+
+        // an invalid version doesn't prevent the entry from being read
+        let contents = entry("abc");
+        let file = DesktopFile::parse(&contents).unwrap();
+        let desktop_entry = DesktopEntry::try_from_file(&file).unwrap();
+        assert_eq!(desktop_entry.common.version, None);
+        assert_eq!(
+            desktop_entry.validate(),
+            [ValidationWarning::InvalidValue("Version")]
+        );
+    }
+
+    #[test]
+    fn categories_validated() {
+        let file = DesktopFile::parse(indoc! {"
+            [Desktop Entry]
+            Type=Application
+            Name=Frobnicator
+            Exec=frob
+            Categories=AudioVideo;Frobnicator;X-Frobbing;Player;
+        "})
+        .unwrap();
+        let desktop_entry = DesktopEntry::try_from_file(&file).unwrap();
+
+        let DesktopEntryType::Application(app) = desktop_entry.for_type else {
+            panic!("expected an application");
+        };
+        let (known, unknown) = app.categories_validated();
+        assert_eq!(known, ["AudioVideo", "X-Frobbing", "Player"]);
+        assert_eq!(unknown, ["Frobnicator"]);
+    }
+
     #[test]
     fn sdrpp() {
         assert_eq!(
@@ -226,6 +1344,110 @@ mod tests {
         );
     }
 
+    #[test]
+    fn expand_love() {
+        let exec = Exec::from_raw("/usr/bin/love %f").unwrap();
+
+        // %f takes a single file, if there is one
+        assert_eq!(
+            exec.expand(&[], None, "LÖVE", None, None),
+            vec!["/usr/bin/love"]
+        );
+        assert_eq!(
+            exec.expand(&["a.love".to_string()], None, "LÖVE", None, None),
+            vec!["/usr/bin/love", "a.love"]
+        );
+        assert_eq!(
+            exec.expand(
+                &["a.love".to_string(), "b.love".to_string()],
+                Some("https://love2d.org"),
+                "LÖVE",
+                None,
+                None
+            ),
+            vec!["/usr/bin/love", "a.love"]
+        );
+    }
+
+    #[test]
+    fn expand_kate() {
+        let exec = Exec::from_raw("kate -b %U").unwrap();
+
+        // %U takes every URL
+        assert_eq!(
+            exec.expand(&[], None, "Kate", None, None),
+            vec!["kate", "-b"]
+        );
+        assert_eq!(
+            exec.expand(
+                &["a.txt".to_string(), "b.txt".to_string()],
+                Some("file:///c.txt"),
+                "Kate",
+                None,
+                None
+            ),
+            vec!["kate", "-b", "file:///c.txt", "a.txt", "b.txt"]
+        );
+    }
+
+    #[test]
+    fn expand_single_and_list_codes() {
+        let files = ["a".to_string(), "b".to_string()];
+
+        let exec = Exec::from_raw("program %F").unwrap();
+        assert_eq!(
+            exec.expand(&files, None, "", None, None),
+            vec!["program", "a", "b"]
+        );
+
+        let exec = Exec::from_raw("program %u").unwrap();
+        assert_eq!(
+            exec.expand(&files, None, "", None, None),
+            vec!["program", "a"]
+        );
+        assert_eq!(
+            exec.expand(&files, Some("https://example.com"), "", None, None),
+            vec!["program", "https://example.com"]
+        );
+    }
+
+    #[test]
+    fn expand_icon_name_and_unknown() {
+        let exec = Exec::from_raw("program %i --name %c %d %D %n %N %v %m %z").unwrap();
+
+        assert_eq!(
+            exec.expand(&[], None, "Program", Some("program-icon"), None),
+            vec!["program", "--icon", "program-icon", "--name", "Program"]
+        );
+        // %i is dropped entirely without an icon
+        assert_eq!(
+            exec.expand(&[], None, "Program", None, None),
+            vec!["program", "--name", "Program"]
+        );
+    }
+
+    #[test]
+    fn expand_location() {
+        let exec = Exec::from_raw("program --file %k --name %c").unwrap();
+        let location = Path::new("/usr/share/applications/program.desktop");
+
+        assert_eq!(
+            exec.expand(&[], None, "Program", None, Some(location)),
+            vec![
+                "program",
+                "--file",
+                "/usr/share/applications/program.desktop",
+                "--name",
+                "Program"
+            ]
+        );
+        // %k is dropped entirely without a location
+        assert_eq!(
+            exec.expand(&[], None, "Program", None, None),
+            vec!["program", "--file", "--name", "Program"]
+        );
+    }
+
     #[test]
     fn openstreetmap_geo_handler() {
         assert_eq!(
@@ -271,6 +1493,31 @@ mod tests {
         );
     }
 
+    #[test]
+    fn quoted_escapes() {
+        // the string escape rule applies first, so a literal `$` is written `\\$` and a literal
+        // backslash `\\\\`
+        assert_eq!(
+            Exec::from_raw(r#"sh -c "echo \\$HOME \\\\ \\" \\`""#).unwrap(),
+            Exec {
+                program: "sh".to_string(),
+                arguments: vec![
+                    ExecArgument::String("-c".to_string()),
+                    ExecArgument::String(r#"echo $HOME \ " `"#.to_string()),
+                ],
+            }
+        );
+
+        // other characters can't be escaped, so the backslash is kept
+        assert_eq!(
+            Exec::from_raw(r#"printf "a\\nb""#).unwrap(),
+            Exec {
+                program: "printf".to_string(),
+                arguments: vec![ExecArgument::String(r"a\nb".to_string())],
+            }
+        );
+    }
+
     #[test]
     fn lone_percent() {
         assert_eq!(
@@ -0,0 +1,164 @@
+//! Compares two desktop files, e.g. to see what changed between two versions of a package.
+use crate::DesktopFile;
+
+/// A difference between two desktop files, found by [`diff`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Change<'a> {
+    /// A group only in the new file. Its keys aren't listed as added.
+    GroupAdded(&'a str),
+    /// A group only in the old file. Its keys aren't listed as removed.
+    GroupRemoved(&'a str),
+    KeyAdded {
+        group: &'a str,
+        key: &'a str,
+        value: &'a str,
+    },
+    KeyRemoved {
+        group: &'a str,
+        key: &'a str,
+        value: &'a str,
+    },
+    /// A key in both files with a different raw value.
+    KeyModified {
+        group: &'a str,
+        key: &'a str,
+        old: &'a str,
+        new: &'a str,
+    },
+}
+
+/// Finds the changes from the `old` file to the `new` file, comparing groups and keys by name and
+/// values as they're written (so e.g. `a;b` and `a;b;` differ). Localized keys are compared as
+/// separate keys, and moving a group or key isn't a change.
+///
+/// Changes are ordered by where they are in `old`, followed by groups and keys only in `new` in
+/// the order they appear. Within each group, removed and modified keys come before added keys.
+pub fn diff<'a>(old: &'a DesktopFile, new: &'a DesktopFile) -> Vec<Change<'a>> {
+    let mut changes = vec![];
+
+    for (group_name, old_group) in old.groups() {
+        let Some(new_group) = new.group(group_name) else {
+            changes.push(Change::GroupRemoved(group_name));
+            continue;
+        };
+
+        for (key, old_value) in old_group.entries() {
+            match new_group.get_raw_exact(key) {
+                None => changes.push(Change::KeyRemoved {
+                    group: group_name,
+                    key,
+                    value: old_value,
+                }),
+                Some(new_value) if new_value != old_value => changes.push(Change::KeyModified {
+                    group: group_name,
+                    key,
+                    old: old_value,
+                    new: new_value,
+                }),
+                Some(_) => {}
+            }
+        }
+
+        for (key, value) in new_group.entries() {
+            if old_group.get_raw_exact(key).is_none() {
+                changes.push(Change::KeyAdded {
+                    group: group_name,
+                    key,
+                    value,
+                });
+            }
+        }
+    }
+
+    for group_name in new.group_names() {
+        if old.group(group_name).is_none() {
+            changes.push(Change::GroupAdded(group_name));
+        }
+    }
+
+    changes
+}
+
+#[cfg(test)]
+mod tests {
+    use indoc::indoc;
+
+    use super::*;
+
+    const OLD: &str = indoc! {"
+        [Desktop Entry]
+        Type=Application
+        Name=Firefox
+        Exec=firefox %u
+        Keywords=web;
+
+        [Desktop Action new-window]
+        Name=New Window
+    "};
+
+    #[test]
+    fn diff_unchanged() {
+        let old = DesktopFile::parse(OLD).unwrap();
+        let moved = DesktopFile::parse(indoc! {"
+            [Desktop Action new-window]
+            Name=New Window
+
+            [Desktop Entry]
+            Name=Firefox
+            Type=Application
+            Keywords=web;
+            Exec=firefox %u
+        "})
+        .unwrap();
+
+        assert_eq!(diff(&old, &old), []);
+        // moving groups and keys isn't a change
+        assert_eq!(diff(&old, &moved), []);
+    }
+
+    #[test]
+    fn diff_changes() {
+        let old = DesktopFile::parse(OLD).unwrap();
+        let new = DesktopFile::parse(indoc! {"
+            [Desktop Entry]
+            Type=Application
+            Name=Firefox
+            Name[de]=Firefox
+            Exec=firefox --new %u
+            Icon=firefox
+
+            [Desktop Action new-private-window]
+            Name=New Private Window
+        "})
+        .unwrap();
+
+        assert_eq!(
+            diff(&old, &new),
+            [
+                Change::KeyModified {
+                    group: "Desktop Entry",
+                    key: "Exec",
+                    old: "firefox %u",
+                    new: "firefox --new %u",
+                },
+                Change::KeyRemoved {
+                    group: "Desktop Entry",
+                    key: "Keywords",
+                    value: "web;",
+                },
+                Change::KeyAdded {
+                    group: "Desktop Entry",
+                    key: "Name[de]",
+                    value: "Firefox",
+                },
+                Change::KeyAdded {
+                    group: "Desktop Entry",
+                    key: "Icon",
+                    value: "firefox",
+                },
+                Change::GroupRemoved("Desktop Action new-window"),
+                Change::GroupAdded("Desktop Action new-private-window"),
+            ]
+        );
+    }
+}
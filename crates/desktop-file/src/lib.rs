@@ -13,6 +13,8 @@
 //! important aspects.
 //! - parser: "Blank line(s)" is interpreted as "empty line(s)". That is, **a blank line is a line
 //!   which contains no characters other than its terminating newline.**
+//! - parser: The specification doesn't say whether the last line must end with a newline. Many
+//!   files don't, so **the newline is optional on the last line of the file** (unless it's blank).
 //! - parser: As pertaining to entries, the specification states that "Space before and after the
 //!   equals sign should be ignored; the `=` sign is the actual delimiter." As far as I can tell,
 //!   this is a contradiction. As such, **spaces before and after the equals sign are ignored, and
@@ -29,8 +31,22 @@
 //!   all when the specification defines keys, and further the expected format of the locale string
 //!   is underspecified. It is simply specified that a key may be suffixed with a locale string
 //!   (e.g. `key[locale]=value`). We implement this such that **the locale string may only contain
-//!   the characters `A-Z`, `a-z`, `0-9`, `-`, `_`, and `@`** (that is, all characters allowed in
-//!   keys plus `_` and `@` to support `LC_MESSAGES` style `lang_COUNTRY@MODIFIER` locale strings).
+//!   the characters `A-Z`, `a-z`, `0-9`, `-`, `_`, `.` and `@`** (that is, all characters allowed
+//!   in keys plus `_`, `.` and `@` to support `LC_MESSAGES` style `lang_COUNTRY.ENCODING@MODIFIER`
+//!   locale strings).
+//!   **The locale string must be non-empty, and a key may only have one locale** (so `Name[]` and
+//!   `Name[de][fr]` are rejected).
+//! - repr: The specification states that the `.ENCODING` part of a locale is ignored when matching
+//!   keys, but some files include it anyway (e.g. `Name[en_US.UTF-8]`). **Encodings are dropped
+//!   when a locale is parsed, so a [`LocalizedKey`] never has one, and are stripped from the keys
+//!   in the file when looking up a localized key.** That is, a lookup for `Name` in `en_US` (or
+//!   `en_US.UTF-8`) matches `Name[en_US.UTF-8]` if there is no `Name[en_US]`.
+//! - values: The escape sequence `\;` is only defined for values which are lists of strings, where it
+//!   allows a string to contain a semicolon. The `string` type simply permits semicolons. As such,
+//!   **when parsed as a `string`, `\;` is not treated as an escape sequence and is kept as-is
+//!   (i.e. `a\;b` parses as `a\;b`), whereas when parsed as `strings`, `\;` is an escaped
+//!   semicolon (i.e. `a\;b` parses as the single string `a;b`).** This allows a value to be read as
+//!   either type without error.
 //! - repr: The specification states that "Multiple groups may not have the same name." I don't see
 //!   how this makes sense - if two groups have the same name, they are the same group. Presumably,
 //!   this is intended to communicate that you cannot add keys to a previously created but not
@@ -47,6 +63,9 @@
 //!   [group1]
 //!   k3=v3
 //!   ```
+//! - repr: Comments aren't associated with anything by the specification. So that they can be
+//!   written back in place, **a comment is attached to the next group header or entry after it**,
+//!   and comments after the last group header or entry are kept separately as trailing comments.
 //!
 //! # Deviations
 //! We deviate from the specification in a few ways, which overall make our parser more lenient.
@@ -56,15 +75,27 @@
 //!     except for `[` and `]` and control characters." We permit all characters except for `[`
 //!     and `]`.
 
+pub mod builder;
 pub mod define_group;
 pub mod desktop_entry;
+pub mod diff;
+pub mod mime;
 pub mod parser;
 mod parser_util;
 
-use std::collections::HashMap;
+use std::borrow::Cow;
+use std::collections::HashSet;
+use std::env;
+use std::fmt;
+use std::io::{self, BufRead};
+use std::mem;
+use std::ops::Range;
+use std::path::{Component, Path, PathBuf};
+
+use indexmap::IndexMap;
 use thiserror::Error;
 
-use parser::{file_parser, value_parser, Line};
+use parser::{file_parser, locale_parser, value_parser, Line};
 
 type PegParseError = peg::error::ParseError<peg::str::LineCol>;
 
@@ -72,18 +103,64 @@ type PegParseError = peg::error::ParseError<peg::str::LineCol>;
 #[error(transparent)]
 pub struct ParseError(#[from] PegParseError);
 
+impl ParseError {
+    /// The 1-based line and column at which parsing failed.
+    pub fn location(&self) -> (usize, usize) {
+        let location = &self.0.location;
+        (location.line, location.column)
+    }
+}
+
 #[derive(Error, Debug, PartialEq)]
 pub enum DesktopFileError<'input> {
-    #[error("parsing should succeed")]
+    #[error(
+        "parsing should succeed (failed at line {} column {})",
+        .0.location().0,
+        .0.location().1
+    )]
     Parse(#[from] ParseError),
     #[error("entries must be preceeded by a group header (found key {0} outside group)")]
-    EntryOutsideOfGroup(&'input str),
+    EntryOutsideOfGroup(&'input str, Range<usize>),
     #[error("a group must appear in one group header only (found duplicate group [{0}])")]
-    DuplicateGroup(&'input str),
+    DuplicateGroup(&'input str, Range<usize>),
     #[error("keys within a group must be unique (found duplicate key {0})")]
-    DuplicateKey(&'input str),
+    DuplicateKey(&'input str, Range<usize>),
+}
+
+impl DesktopFileError<'_> {
+    /// The byte range of the line which caused the error (including its line ending), or [`None`]
+    /// for a [`DesktopFileError::Parse`] error, which has a [`ParseError::location`] instead.
+    pub fn span(&self) -> Option<Range<usize>> {
+        match self {
+            Self::Parse(_) => None,
+            Self::EntryOutsideOfGroup(_, span)
+            | Self::DuplicateGroup(_, span)
+            | Self::DuplicateKey(_, span) => Some(span.clone()),
+        }
+    }
 }
 
+/// A problem found by [`DesktopFile::parse_with_warnings`] which was worked around, where
+/// [`DesktopFile::parse`] would have returned an error.
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum Warning<'input> {
+    /// The last value of the key was kept.
+    #[error("keys within a group must be unique (found duplicate key {key} in [{group}])")]
+    DuplicateKey {
+        group: &'input str,
+        key: &'input str,
+    },
+    /// The entries of each group header were merged into one group.
+    #[error("a group must appear in one group header only (found duplicate group [{group}])")]
+    DuplicateGroup { group: &'input str },
+}
+
+/// More than one group matched a lookup with [`DesktopFile::group_ignore_case`]. Contains the names
+/// of the groups which matched.
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+#[error("groups {0:?} only differ by case")]
+pub struct AmbiguousGroup(pub Vec<String>);
+
 /// Required to turn a [PegParseError] and into a [DesktopFileError] with `?`.
 impl From<PegParseError> for DesktopFileError<'_> {
     fn from(value: PegParseError) -> Self {
@@ -91,88 +168,647 @@ impl From<PegParseError> for DesktopFileError<'_> {
     }
 }
 
-#[derive(Debug)]
+/// An error encountered while reading a desktop file with [`DesktopFile::from_reader`]. Unlike
+/// [`DesktopFileError`], keys and group names are owned, as the lines they were read from aren't
+/// kept.
+#[derive(Error, Debug)]
+pub enum ReadError {
+    #[error("reading should succeed")]
+    Io(#[from] io::Error),
+    #[error(
+        "parsing should succeed (failed at line {} column {})",
+        .0.location().0,
+        .0.location().1
+    )]
+    Parse(#[from] ParseError),
+    #[error("entries must be preceeded by a group header (found key {0} outside group)")]
+    EntryOutsideOfGroup(String),
+    #[error("a group must appear in one group header only (found duplicate group [{0}])")]
+    DuplicateGroup(String),
+    #[error("keys within a group must be unique (found duplicate key {0})")]
+    DuplicateKey(String),
+}
+
+/// A [`DesktopFile`] which owns all of its groups and entries, and so doesn't borrow from the
+/// source it was parsed from.
+pub type OwnedDesktopFile = DesktopFile<'static>;
+
+/// A [`Group`] which owns all of its entries.
+pub type OwnedGroup = Group<'static>;
+
+/// A desktop file's groups, in the order they appear. The default is an empty file.
+#[derive(Debug, PartialEq, Default)]
 pub struct DesktopFile<'input> {
-    groups: HashMap<&'input str, Group<'input>>,
+    groups: IndexMap<Cow<'input, str>, Group<'input>>,
+    /// Comments after the last group header or entry.
+    trailing_comments: Vec<Cow<'input, str>>,
 }
 
 impl<'input> DesktopFile<'input> {
-    pub fn parse(s: &'input str) -> Result<Self, DesktopFileError> {
-        let lines = file_parser::file(s)?;
+    pub fn parse(s: &'input str) -> Result<Self, DesktopFileError<'input>> {
+        Self::parse_filtered(s, |_| true, DuplicateKeys::Error).map(|(file, _)| file)
+    }
+
+    /// Like [`DesktopFile::parse`], but a key repeated within a group is accepted rather than being
+    /// a [`DesktopFileError::DuplicateKey`] error. This isn't permitted by the specification, but is
+    /// found in real-world files.
+    ///
+    /// The first value of a repeated key is returned by [`Group::get_raw`] and friends, and every
+    /// value can be read with [`Group::get_all`].
+    pub fn parse_lenient(s: &'input str) -> Result<Self, DesktopFileError<'input>> {
+        Self::parse_filtered(s, |_| true, DuplicateKeys::Collect).map(|(file, _)| file)
+    }
+
+    /// Like [`DesktopFile::parse`], but repeated keys and groups are accepted with a [`Warning`]
+    /// rather than being an error, for best-effort reading of slightly broken files.
+    ///
+    /// The last value of a repeated key wins, and the entries of a repeated group are merged into
+    /// the group where it first appeared. Errors which can't be worked around (such as a line which
+    /// can't be parsed) are still returned.
+    pub fn parse_with_warnings(
+        s: &'input str,
+    ) -> Result<(Self, Vec<Warning<'input>>), DesktopFileError<'input>> {
+        Self::parse_filtered(s, |_| true, DuplicateKeys::Overwrite)
+    }
 
-        let mut groups = HashMap::new();
+    /// Parses a desktop file, but only retains the group named `group_name`. Entries in other
+    /// groups are skipped without being stored, which is cheaper when only one group (typically
+    /// `Desktop Entry`) will be read.
+    ///
+    /// The file is still checked for duplicate groups and entries outside of a group, but duplicate
+    /// keys are only detected within the retained group.
+    pub fn parse_group(s: &'input str, group_name: &str) -> Result<Self, DesktopFileError<'input>> {
+        Self::parse_filtered(s, |name| name == group_name, DuplicateKeys::Error)
+            .map(|(file, _)| file)
+    }
+
+    fn parse_filtered(
+        s: &'input str,
+        retain: impl Fn(&str) -> bool,
+        duplicate_keys: DuplicateKeys,
+    ) -> Result<(Self, Vec<Warning<'input>>), DesktopFileError<'input>> {
+        let (s, bom_len) = strip_bom(s);
+        let lines = file_parser::file(s).map_err(|mut err| {
+            err.location.offset += bom_len;
+            err
+        })?;
+
+        let mut warnings = vec![];
+        let mut groups: IndexMap<Cow<str>, Group> = IndexMap::new();
+        let mut group_names = HashSet::new();
         let mut current_group_name = None;
-        for line in lines {
+        // comments since the last group header or entry, which they're attached to
+        let mut comments = vec![];
+        for (line, span) in lines {
+            // spans are of the input as given, including the BOM
+            let span = span.start + bom_len..span.end + bom_len;
             match line {
-                Line::Blank | Line::Comment(_) => {}
+                Line::Blank => {}
+                Line::Comment(comment) => comments.push(comment.into()),
                 Line::GroupHeader(group_name) => {
-                    if groups.insert(group_name, Group::new()).is_some() {
-                        return Err(DesktopFileError::DuplicateGroup(group_name));
-                    }
+                    let comments = mem::take(&mut comments);
                     current_group_name = Some(group_name);
+                    if !group_names.insert(group_name) {
+                        let DuplicateKeys::Overwrite = duplicate_keys else {
+                            return Err(DesktopFileError::DuplicateGroup(group_name, span));
+                        };
+                        warnings.push(Warning::DuplicateGroup { group: group_name });
+                        if let Some(group) = groups.get_mut(group_name) {
+                            group.comments.extend(comments);
+                        }
+                        continue;
+                    }
+                    if retain(group_name) {
+                        let mut group = Group::new();
+                        group.comments = comments;
+                        groups.insert(group_name.into(), group);
+                    }
+                }
+                Line::Entry(key, value) => {
+                    let Some(group_name) = current_group_name else {
+                        return Err(DesktopFileError::EntryOutsideOfGroup(key, span));
+                    };
+
+                    let comments = mem::take(&mut comments);
+                    // skipped groups aren't in the map
+                    let Some(group) = groups.get_mut(group_name) else {
+                        continue;
+                    };
+                    group.add_comments(key, comments);
+                    if !group.entries.contains_key(key) {
+                        group.entries.insert(key.into(), value.into());
+                        continue;
+                    }
+                    match duplicate_keys {
+                        DuplicateKeys::Error => {
+                            return Err(DesktopFileError::DuplicateKey(key, span))
+                        }
+                        DuplicateKeys::Collect => group
+                            .repeated
+                            .entry(key.into())
+                            .or_default()
+                            .push(value.into()),
+                        DuplicateKeys::Overwrite => {
+                            warnings.push(Warning::DuplicateKey {
+                                group: group_name,
+                                key,
+                            });
+                            group.entries.insert(key.into(), value.into());
+                        }
+                    }
+                }
+            }
+        }
+
+        let file = Self {
+            groups,
+            trailing_comments: comments,
+        };
+        Ok((file, warnings))
+    }
+
+    /// Parses a desktop file line by line from `reader`, rather than requiring the entire file to
+    /// be read into memory first. Only one line is held at a time, so the parsed file owns its
+    /// groups and entries.
+    pub fn from_reader<R: BufRead>(mut reader: R) -> Result<OwnedDesktopFile, ReadError> {
+        let mut groups: IndexMap<Cow<str>, Group> = IndexMap::new();
+        let mut comments = vec![];
+        let mut line = String::new();
+        let mut offset = 0;
+        for line_number in 1.. {
+            line.clear();
+            let len = reader.read_line(&mut line)?;
+            if len == 0 {
+                break;
+            }
+
+            let (content, bom_len) = match line_number {
+                1 => strip_bom(&line),
+                _ => (line.as_str(), 0),
+            };
+            // only the last line can be missing its linefeed
+            let parsed = if content.ends_with('\n') {
+                file_parser::line(content)
+            } else {
+                file_parser::last_line(content)
+            };
+            // each line is parsed on its own, so errors must be moved to where the line starts
+            let parsed = parsed.map_err(|mut err| {
+                err.location.line = line_number;
+                err.location.offset += offset + bom_len;
+                ParseError(err)
+            })?;
+            offset += len;
+
+            match parsed {
+                Line::Blank => {}
+                Line::Comment(comment) => comments.push(comment.to_string().into()),
+                Line::GroupHeader(group_name) => {
+                    if groups.contains_key(group_name) {
+                        return Err(ReadError::DuplicateGroup(group_name.to_string()));
+                    }
+                    let mut group = Group::new();
+                    group.comments = mem::take(&mut comments);
+                    groups.insert(group_name.to_string().into(), group);
                 }
                 Line::Entry(key, value) => {
-                    let group_name =
-                        current_group_name.ok_or(DesktopFileError::EntryOutsideOfGroup(key))?;
-
-                    let group = groups
-                        .get_mut(group_name)
-                        .expect("current group should exist");
-                    if group.entries.insert(key, value).is_some() {
-                        return Err(DesktopFileError::DuplicateKey(key));
+                    // entries always belong to the most recent group header
+                    let Some((_, group)) = groups.last_mut() else {
+                        return Err(ReadError::EntryOutsideOfGroup(key.to_string()));
+                    };
+                    if group.entries.contains_key(key) {
+                        return Err(ReadError::DuplicateKey(key.to_string()));
                     }
+                    group.add_comments(key.to_string(), mem::take(&mut comments));
+                    group
+                        .entries
+                        .insert(key.to_string().into(), value.to_string().into());
                 }
             }
         }
 
-        Ok(Self { groups })
+        Ok(DesktopFile {
+            groups,
+            trailing_comments: comments,
+        })
     }
 
-    pub fn group(&self, group_name: &str) -> Option<&Group> {
+    pub fn group(&self, group_name: &str) -> Option<&Group<'input>> {
         self.groups.get(group_name)
     }
 
-    pub fn groups(&self) -> impl Iterator<Item = (&str, &Group)> {
+    /// Like [`DesktopFile::group`], but ignores the case of the group names. It's an error for more
+    /// than one group to match, as they only differ by case.
+    pub fn group_ignore_case(
+        &self,
+        group_name: &str,
+    ) -> Result<Option<&Group<'input>>, AmbiguousGroup> {
+        let group_name = group_name.to_lowercase();
+        let matches = self
+            .groups
+            .iter()
+            .filter(|(name, _)| name.to_lowercase() == group_name)
+            .collect::<Vec<_>>();
+
+        match matches.as_slice() {
+            [] => Ok(None),
+            [(_, group)] => Ok(Some(group)),
+            _ => {
+                let names = matches.iter().map(|(name, _)| name.to_string()).collect();
+                Err(AmbiguousGroup(names))
+            }
+        }
+    }
+
+    pub fn group_mut(&mut self, group_name: &str) -> Option<&mut Group<'input>> {
+        self.groups.get_mut(group_name)
+    }
+
+    /// Returns the group with the given name, inserting a new empty group at the end of the file if
+    /// it doesn't already exist.
+    pub fn insert_group(&mut self, group_name: impl Into<Cow<'input, str>>) -> &mut Group<'input> {
+        self.groups
+            .entry(group_name.into())
+            .or_insert_with(Group::new)
+    }
+
+    pub fn groups(&self) -> impl Iterator<Item = (&str, &Group<'input>)> {
         self.groups
             .iter()
-            .map(|(group_name, group)| (*group_name, group))
+            .map(|(group_name, group)| (group_name.as_ref(), group))
+    }
+
+    /// Returns the names of the groups, in the order they appear.
+    pub fn group_names(&self) -> impl Iterator<Item = &str> {
+        self.groups.keys().map(AsRef::as_ref)
+    }
+
+    /// Returns the groups whose names start with `prefix`, in the order they appear. For example,
+    /// the prefix `Desktop Action ` gives every action group.
+    pub fn groups_with_prefix<'a>(
+        &'a self,
+        prefix: &'a str,
+    ) -> impl Iterator<Item = (&'a str, &'a Group<'input>)> {
+        self.groups()
+            .filter(move |(group_name, _)| group_name.starts_with(prefix))
+    }
+
+    /// Merges `other` into this file, with `other` taking precedence. Groups only in `other` are
+    /// added to the end of the file, and groups in both are merged with [`Group::merge`].
+    ///
+    /// This is useful where one file overrides another, e.g. a user's copy of a system-wide
+    /// desktop entry.
+    pub fn merge(&mut self, other: DesktopFile<'input>) {
+        for (group_name, group) in other.groups {
+            match self.groups.get_mut(&group_name) {
+                Some(existing) => existing.merge(group),
+                None => {
+                    self.groups.insert(group_name, group);
+                }
+            }
+        }
+        if !other.trailing_comments.is_empty() {
+            self.trailing_comments = other.trailing_comments;
+        }
+    }
+
+    /// Returns the comments at the end of the file, after the last group header or entry, without
+    /// their leading `#`.
+    pub fn trailing_comments(&self) -> impl Iterator<Item = &str> {
+        self.trailing_comments
+            .iter()
+            .map(|comment| comment.as_ref())
+    }
+
+    /// Copies any group names, keys and values borrowed from the source the file was parsed from,
+    /// so the file can outlive it (e.g. to be stored in a struct or sent to another thread).
+    pub fn into_owned(self) -> OwnedDesktopFile {
+        let groups = self
+            .groups
+            .into_iter()
+            .map(|(group_name, group)| (owned(group_name), group.into_owned()))
+            .collect();
+        let trailing_comments = self.trailing_comments.into_iter().map(owned).collect();
+
+        DesktopFile {
+            groups,
+            trailing_comments,
+        }
+    }
+}
+
+/// Writes the file back out in desktop entry file format. Groups and entries are written in the
+/// order they were parsed in, with values written exactly as they appeared in the source. Repeated
+/// keys (see [`DesktopFile::parse_lenient`]) are written on consecutive lines. Comments are written
+/// before the group header or entry they're attached to, but blank lines are not preserved.
+impl fmt::Display for DesktopFile<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let write_comments = |f: &mut fmt::Formatter<'_>, comments: &[Cow<str>]| {
+            comments
+                .iter()
+                .try_for_each(|comment| writeln!(f, "#{comment}"))
+        };
+
+        for (i, (group_name, group)) in self.groups().enumerate() {
+            if i != 0 {
+                writeln!(f)?;
+            }
+
+            write_comments(f, &group.comments)?;
+            writeln!(f, "[{group_name}]")?;
+            for (key, _) in group.entries() {
+                if let Some(comments) = group.entry_comments.get(key) {
+                    write_comments(f, comments)?;
+                }
+                for value in group.get_all(key) {
+                    writeln!(f, "{key}={value}")?;
+                }
+            }
+        }
+        write_comments(f, &self.trailing_comments)?;
+
+        Ok(())
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, PartialEq)]
 pub struct Group<'input> {
-    entries: HashMap<&'input str, &'input str>,
+    entries: IndexMap<Cow<'input, str>, Cow<'input, str>>,
+    /// Values after the first of keys which are repeated, if parsed leniently.
+    repeated: IndexMap<Cow<'input, str>, Vec<Cow<'input, str>>>,
+    /// Comments before the group header.
+    comments: Vec<Cow<'input, str>>,
+    /// Comments before each entry, by key.
+    entry_comments: IndexMap<Cow<'input, str>, Vec<Cow<'input, str>>>,
+}
+
+/// Copies a borrowed string, so it no longer borrows from the source it was parsed from.
+fn owned(s: Cow<str>) -> Cow<'static, str> {
+    Cow::Owned(s.into_owned())
+}
+
+/// What to do when a key appears more than once within a group.
+#[derive(Clone, Copy)]
+enum DuplicateKeys {
+    Error,
+    Collect,
+    /// Keep the last value with a warning. Repeated groups are also merged with a warning, rather
+    /// than being an error.
+    Overwrite,
 }
 
-impl Group<'_> {
+impl<'input> Group<'input> {
     fn new() -> Self {
         Self {
-            entries: HashMap::new(),
+            entries: IndexMap::new(),
+            repeated: IndexMap::new(),
+            comments: vec![],
+            entry_comments: IndexMap::new(),
+        }
+    }
+
+    /// Attaches `comments` to the entry with the given key, after any already attached.
+    fn add_comments(&mut self, key: impl Into<Cow<'input, str>>, comments: Vec<Cow<'input, str>>) {
+        if comments.is_empty() {
+            return;
         }
+
+        self.entry_comments
+            .entry(key.into())
+            .or_default()
+            .extend(comments);
+    }
+
+    /// Returns the comments before the group header, without their leading `#`.
+    pub fn comments(&self) -> impl Iterator<Item = &str> {
+        self.comments.iter().map(|comment| comment.as_ref())
+    }
+
+    /// Returns the comments before the entry with the given key, without their leading `#`.
+    pub fn comments_for(&self, key: &str) -> impl Iterator<Item = &str> {
+        self.entry_comments
+            .get(key)
+            .into_iter()
+            .flatten()
+            .map(|comment| comment.as_ref())
     }
 
+    /// Returns the raw value for `key`. A [`LocalizedKey`] falls back to less specific locales and
+    /// then the unlocalized key, whereas a string key (even one with a locale, e.g. `Name[de]`) is
+    /// looked up exactly as with [`Group::get_raw_exact`].
     pub fn get_raw<'a>(&self, key: impl Into<Key<'a>>) -> Option<&str> {
-        let entry = match key.into() {
-            Key::String(key) => self.entries.get(key),
+        match key.into() {
+            Key::String(key) => self.get_raw_exact(key),
             Key::Localized(locale_key) => locale_key
                 .matches()
-                .into_iter()
-                .flat_map(|key| self.entries.get(key.as_str()))
-                .next(),
-        };
+                .iter()
+                .find_map(|key| self.get_raw_ignoring_encoding(key)),
+        }
+    }
+
+    /// Returns the raw value for exactly `key` (e.g. `Name[de_DE]`), or for the same key with an
+    /// encoding in the file (e.g. `Name[de_DE.UTF-8]`).
+    pub(crate) fn get_raw_ignoring_encoding(&self, key: &str) -> Option<&str> {
+        let entry = self.entries.get(key).or_else(|| {
+            self.entries
+                .iter()
+                .find(|(entry_key, _)| strip_encoding(entry_key) == key)
+                .map(|(_, value)| value)
+        });
+
+        entry.map(|value| value.as_ref())
+    }
+
+    /// Returns the raw value for exactly `key` (e.g. `Name[de]`), without falling back to other
+    /// locales. Useful to check whether a particular translation is present.
+    pub fn get_raw_exact(&self, key: &str) -> Option<&str> {
+        self.entries.get(key).map(|value| value.as_ref())
+    }
+
+    /// Whether the group has a value for `key`. Localized keys fall back to less specific locales
+    /// in the same way as [`Group::get_raw`].
+    pub fn contains_key<'a>(&self, key: impl Into<Key<'a>>) -> bool {
+        self.get_raw(key).is_some()
+    }
+
+    /// The number of distinct keys in the group, with each localized key counted separately.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
 
-        entry.copied()
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
     }
 
     pub fn get<'a, V: FromRaw>(&self, key: impl Into<Key<'a>>) -> Option<Result<V, ParseError>> {
         self.get_raw(key).map(|value| V::from_raw(value))
     }
 
+    /// Like [`Group::get`], but returns `default` if `key` isn't present. A value which is present
+    /// but can't be parsed is still an error.
+    pub fn get_or<'a, V: FromRaw>(
+        &self,
+        key: impl Into<Key<'a>>,
+        default: V,
+    ) -> Result<V, ParseError> {
+        self.get(key).unwrap_or(Ok(default))
+    }
+
+    /// Like [`Group::get`], but parses the value leniently with [`FromRawLenient`].
+    pub fn get_lenient<'a, V: FromRawLenient>(
+        &self,
+        key: impl Into<Key<'a>>,
+    ) -> Option<Result<V, ParseError>> {
+        self.get_raw(key).map(|value| V::from_raw_lenient(value))
+    }
+
+    /// Returns every value of `key` in the order they appeared, which is more than one value only if
+    /// the key was repeated in a file parsed with [`DesktopFile::parse_lenient`].
+    pub fn get_all(&self, key: &str) -> Vec<&str> {
+        let first = self.entries.get(key);
+        let repeated = self.repeated.get(key).into_iter().flatten();
+
+        first
+            .into_iter()
+            .chain(repeated)
+            .map(|value| value.as_ref())
+            .collect()
+    }
+
     pub fn entries(&self) -> impl Iterator<Item = (&str, &str)> {
-        self.entries.iter().map(|(key, value)| (*key, *value))
+        self.entries
+            .iter()
+            .map(|(key, value)| (key.as_ref(), value.as_ref()))
+    }
+
+    /// Returns the raw entries with extension keys (i.e. keys beginning with `X-`), including
+    /// localized extension keys.
+    pub fn extensions(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.entries().filter(|(key, _)| key.starts_with("X-"))
+    }
+
+    /// Returns the locales `key` is localized in within this group, in the order they appear. The
+    /// unlocalized `key` itself is not included, and keys with malformed locales (e.g. `Name[_AT]`)
+    /// are skipped.
+    pub fn locales_for<'a>(&'a self, key: &'a str) -> Vec<LocalizedKey<'a>> {
+        self.entries
+            .keys()
+            .flat_map(|entry_key| parse_key_with_locale(entry_key).1)
+            .filter(|localized_key| localized_key.key == key)
+            .collect()
+    }
+
+    /// Returns the raw entries along with the key split into its parts if it's localized. Keys
+    /// without a locale, or with a malformed locale (e.g. `Name[_AT]`), have no [`LocalizedKey`].
+    pub fn localized_entries(
+        &self,
+    ) -> impl Iterator<Item = (&str, Option<LocalizedKey<'_>>, &str)> {
+        self.entries()
+            .map(|(key, value)| (key, parse_key_with_locale(key).1, value))
+    }
+
+    /// Sets the raw value of `key`, returning the previous value if there was one. New keys are
+    /// inserted at the end of the group.
+    ///
+    /// Neither the key nor value are validated, so care must be taken to ensure they are valid
+    /// (e.g. that the value is correctly escaped and does not contain a newline).
+    pub fn set_raw(
+        &mut self,
+        key: impl Into<Cow<'input, str>>,
+        value: impl Into<Cow<'input, str>>,
+    ) -> Option<String> {
+        let key = key.into();
+        self.repeated.shift_remove(&key);
+        self.entries.insert(key, value.into()).map(Cow::into_owned)
+    }
+
+    /// Merges the entries of `other` into this group, with `other` taking precedence. Each entry in
+    /// `other` replaces the entry with the same key (along with its comments, if it has any), or is
+    /// added to the end of the group if there isn't one.
+    pub fn merge(&mut self, other: Group<'input>) {
+        let Group {
+            entries,
+            mut repeated,
+            comments,
+            mut entry_comments,
+        } = other;
+
+        if !comments.is_empty() {
+            self.comments = comments;
+        }
+        for (key, value) in entries {
+            if let Some(values) = repeated.shift_remove(&key) {
+                self.repeated.insert(key.clone(), values);
+            } else {
+                self.repeated.shift_remove(&key);
+            }
+            if let Some(comments) = entry_comments.shift_remove(&key) {
+                self.entry_comments.insert(key.clone(), comments);
+            }
+            self.entries.insert(key, value);
+        }
+    }
+
+    /// Removes `key` from the group, returning its raw value if it was present.
+    pub fn remove(&mut self, key: &str) -> Option<String> {
+        self.repeated.shift_remove(key);
+        self.entry_comments.shift_remove(key);
+        self.entries.shift_remove(key).map(Cow::into_owned)
+    }
+
+    /// Copies any keys and values borrowed from the source the group was parsed from. See
+    /// [`DesktopFile::into_owned`].
+    pub fn into_owned(self) -> OwnedGroup {
+        fn owned_values(
+            values: IndexMap<Cow<str>, Vec<Cow<str>>>,
+        ) -> IndexMap<Cow<'static, str>, Vec<Cow<'static, str>>> {
+            values
+                .into_iter()
+                .map(|(key, values)| (owned(key), values.into_iter().map(owned).collect()))
+                .collect()
+        }
+
+        let entries = self
+            .entries
+            .into_iter()
+            .map(|(key, value)| (owned(key), owned(value)))
+            .collect();
+
+        Group {
+            entries,
+            repeated: owned_values(self.repeated),
+            comments: self.comments.into_iter().map(owned).collect(),
+            entry_comments: owned_values(self.entry_comments),
+        }
     }
 }
 
+/// Removes the byte order mark from the start of `s`, if it has one, returning the rest of `s` and
+/// the length of the BOM in bytes. Files written on Windows often begin with a BOM, which would
+/// otherwise be parsed as part of the first line.
+fn strip_bom(s: &str) -> (&str, usize) {
+    const BOM: char = '\u{FEFF}';
+
+    match s.strip_prefix(BOM) {
+        Some(rest) => (rest, BOM.len_utf8()),
+        None => (s, 0),
+    }
+}
+
+/// Removes the `.ENCODING` part of the locale from a localized key, e.g. `Name[en_US.UTF-8@euro]`
+/// becomes `Name[en_US@euro]`. Other keys are returned as-is.
+fn strip_encoding(key: &str) -> Cow<'_, str> {
+    let Some((key_and_locale, encoding)) = key.split_once('.') else {
+        return Cow::Borrowed(key);
+    };
+    let rest = encoding
+        .find(['@', ']'])
+        .map(|end| &encoding[end..])
+        .unwrap_or_default();
+
+    Cow::Owned(format!("{key_and_locale}{rest}"))
+}
+
 pub enum Key<'a> {
     String(&'a str),
     Localized(LocalizedKey<'a>),
@@ -186,7 +822,7 @@ pub struct LocalizedKey<'a> {
     pub modifier: Option<&'a str>,
 }
 
-impl LocalizedKey<'_> {
+impl<'a> LocalizedKey<'a> {
     fn matches(&self) -> Vec<String> {
         let (key, lang) = (self.key, self.lang);
         let mut matches = Vec::with_capacity(5);
@@ -216,6 +852,77 @@ impl LocalizedKey<'_> {
     }
 }
 
+/// A locale of the form `lang_COUNTRY@MODIFIER`, where the `_COUNTRY` and `@MODIFIER` parts are
+/// optional.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Locale {
+    pub lang: String,
+    pub country: Option<String>,
+    pub modifier: Option<String>,
+}
+
+impl Locale {
+    /// Parses a locale string such as `sr_YU@Latn`, returning [`None`] if it is not a valid locale.
+    /// An encoding (e.g. the `.UTF-8` in `en_US.UTF-8`) is allowed, but ignored.
+    pub fn parse(s: &str) -> Option<Self> {
+        let (lang, country, modifier) = locale_parser::locale(s).ok()?;
+
+        Some(Self {
+            lang: lang.to_string(),
+            country: country.map(str::to_string),
+            modifier: modifier.map(str::to_string),
+        })
+    }
+
+    /// The locale messages should be displayed in, from the first of `$LC_ALL`, `$LC_MESSAGES` and
+    /// `$LANG` which is set and non-empty, as per POSIX. Returns [`None`] if none are set, or the
+    /// locale is `C` or `POSIX` (i.e. no localization).
+    pub fn current() -> Option<Self> {
+        let value = ["LC_ALL", "LC_MESSAGES", "LANG"]
+            .into_iter()
+            .flat_map(env::var)
+            .find(|value| !value.is_empty())?;
+
+        Self::parse_posix(&value)
+    }
+
+    /// Parses a POSIX locale such as `en_US.UTF-8`. Returns [`None`] for the `C` and `POSIX`
+    /// locales, or if the locale is otherwise invalid.
+    fn parse_posix(s: &str) -> Option<Self> {
+        let locale = Self::parse(s)?;
+        if locale.lang == "C" || locale.lang == "POSIX" {
+            return None;
+        }
+
+        Some(locale)
+    }
+
+    /// Creates a [`LocalizedKey`] to look up `key` in this locale.
+    pub fn key<'a>(&'a self, key: &'a str) -> LocalizedKey<'a> {
+        LocalizedKey {
+            key,
+            lang: &self.lang,
+            country: self.country.as_deref(),
+            modifier: self.modifier.as_deref(),
+        }
+    }
+}
+
+/// Formats the locale as it appears in a key, i.e. `lang_COUNTRY@MODIFIER`.
+impl fmt::Display for Locale {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.lang)?;
+        if let Some(country) = &self.country {
+            write!(f, "_{country}")?;
+        }
+        if let Some(modifier) = &self.modifier {
+            write!(f, "@{modifier}")?;
+        }
+
+        Ok(())
+    }
+}
+
 impl<'a> From<&'a str> for Key<'a> {
     fn from(value: &'a str) -> Self {
         Self::String(value)
@@ -239,6 +946,49 @@ impl FromRaw for String {
     }
 }
 
+/// A value of type `iconstring` - either the path to an icon file, or the name of an icon to look
+/// up in the icon theme.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum IconString {
+    Path(PathBuf),
+    Name(String),
+}
+
+impl IconString {
+    /// Extensions of the image formats icons are found in, per the Icon Theme Specification.
+    const EXTENSIONS: [&'static str; 3] = ["png", "svg", "xpm"];
+}
+
+/// Parses values of type `iconstring`. Absolute paths, and values with an image file extension
+/// (e.g. `icon.png`), are icon paths. Any other value is an icon name.
+impl FromRaw for IconString {
+    fn from_raw(value: &str) -> Result<Self, ParseError> {
+        let value = String::from_raw(value)?;
+        let path = Path::new(&value);
+
+        let has_image_extension = path
+            .extension()
+            .and_then(|extension| extension.to_str())
+            .is_some_and(|extension| {
+                Self::EXTENSIONS
+                    .iter()
+                    .any(|known| extension.eq_ignore_ascii_case(known))
+            });
+        if path.is_absolute() || has_image_extension {
+            Ok(Self::Path(value.into()))
+        } else {
+            Ok(Self::Name(value))
+        }
+    }
+}
+
+/// Parses values of type `string` which are paths, such as the `Path` key.
+impl FromRaw for PathBuf {
+    fn from_raw(value: &str) -> Result<Self, ParseError> {
+        Ok(String::from_raw(value)?.into())
+    }
+}
+
 /// Parses values of types `strings`, `localestrings` and `iconstrings`.
 impl FromRaw for Vec<String> {
     fn from_raw(value: &str) -> Result<Self, ParseError> {
@@ -246,6 +996,36 @@ impl FromRaw for Vec<String> {
     }
 }
 
+/// Parses values of type `strings` which are lists of paths.
+impl FromRaw for Vec<PathBuf> {
+    fn from_raw(value: &str) -> Result<Self, ParseError> {
+        let paths = value_parser::strings(value)?;
+        Ok(paths.into_iter().map(PathBuf::from).collect())
+    }
+}
+
+/// A list of strings separated by `SEP`, for non-conforming files which separate lists with
+/// something other than `;` (e.g. `Categories=Network,WebBrowser`). As with `strings`, the
+/// separator can be escaped with a backslash, and the list may end with it.
+///
+/// Lists separated by `;` should be read as a [`Vec`] instead.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SepList<const SEP: char, T = String>(pub Vec<T>);
+
+impl<const SEP: char, T: From<String>> FromRaw for SepList<SEP, T> {
+    fn from_raw(value: &str) -> Result<Self, ParseError> {
+        let strings = value_parser::separated_strings(value, SEP)?;
+        Ok(Self(strings.into_iter().map(T::from).collect()))
+    }
+}
+
+/// Parses values of type `string` which are a single character.
+impl FromRaw for char {
+    fn from_raw(value: &str) -> Result<Self, ParseError> {
+        Ok(value_parser::character(value)?)
+    }
+}
+
 /// Parses values of type `boolean`.
 impl FromRaw for bool {
     fn from_raw(value: &str) -> Result<Self, ParseError> {
@@ -253,11 +1033,100 @@ impl FromRaw for bool {
     }
 }
 
+/// Parses values leniently, accepting values which aren't permitted by the specification but are
+/// found in older or non-conforming files.
+pub trait FromRawLenient: Sized {
+    fn from_raw_lenient(raw: &str) -> Result<Self, ParseError>;
+}
+
+/// Parses values of type `boolean`, also accepting `0` and `1` (as used before version 1.0 of the
+/// specification) and any capitalisation of `true` and `false`.
+impl FromRawLenient for bool {
+    fn from_raw_lenient(value: &str) -> Result<Self, ParseError> {
+        Ok(value_parser::boolean_lenient(value)?)
+    }
+}
+
+/// Parses integer values, which may have a sign, for extension keys.
+impl FromRaw for i64 {
+    fn from_raw(value: &str) -> Result<Self, ParseError> {
+        Ok(value_parser::integer(value)?)
+    }
+}
+
+/// Parses unsigned integer values, for extension keys.
+impl FromRaw for u64 {
+    fn from_raw(value: &str) -> Result<Self, ParseError> {
+        Ok(value_parser::unsigned_integer(value)?)
+    }
+}
+
+/// Parses values of type `numeric`.
+impl FromRaw for f64 {
+    fn from_raw(value: &str) -> Result<Self, ParseError> {
+        Ok(value_parser::numeric(value)?)
+    }
+}
+
+/// Splits a key from a file (e.g. `Name[sr_YU@Latn]`) into the key without its locale, and the
+/// key's parts if it's localized.
+///
+/// Keys without a locale, or with a malformed locale (e.g. `Name[]` or `Name[de`), are returned
+/// as-is with no [`LocalizedKey`].
+pub fn parse_key_with_locale(key: &str) -> (&str, Option<LocalizedKey<'_>>) {
+    match locale_parser::localized_key(key) {
+        Ok((key, (lang, country, modifier))) => (
+            key,
+            Some(LocalizedKey {
+                key,
+                lang,
+                country,
+                modifier,
+            }),
+        ),
+        Err(_) => (key, None),
+    }
+}
+
+/// Computes the desktop file ID of `file`, relative to the `applications` directory `base` it was
+/// found in.
+///
+/// As per the specification, subdirectories of `base` are joined with `-`. The `.desktop` suffix is
+/// stripped, such that `base/kde/foo.desktop` has the ID `kde-foo`.
+///
+/// Returns [`None`] if `file` is not within `base`, is not a `.desktop` file, or its path is not
+/// valid UTF-8.
+pub fn desktop_file_id(base: &Path, file: &Path) -> Option<String> {
+    let relative = file.strip_prefix(base).ok()?;
+
+    let components = relative
+        .components()
+        .map(|component| match component {
+            Component::Normal(component) => component.to_str(),
+            _ => None,
+        })
+        .collect::<Option<Vec<_>>>()?;
+
+    let id = components.join("-");
+    let id = id.strip_suffix(".desktop")?;
+    if id.is_empty() {
+        return None;
+    }
+
+    Some(id.to_string())
+}
+
 #[cfg(test)]
 mod tests {
+    use std::io::Cursor;
+    use std::path::{Path, PathBuf};
+
     use indoc::indoc;
 
-    use super::{DesktopFile, DesktopFileError, LocalizedKey};
+    use super::{
+        desktop_file_id, parse_key_with_locale, AmbiguousGroup, DesktopFile, DesktopFileError,
+        FromRaw, IconString, Locale, LocalizedKey, ReadError, SepList, Warning,
+    };
 
     #[test]
     fn desktop_file_empty() {
@@ -289,35 +1158,461 @@ mod tests {
     }
 
     #[test]
-    fn desktop_file_localized() {
+    fn desktop_file_to_string() {
         let file = DesktopFile::parse(indoc! {"
-            [group]
-            Name=default value
-            Name[sr_YU]=localized sr_YU
-            Name[sr@Latn]=localized sr@Latn
-            Name[sr]=localized sr
+            # comments are kept, but blank lines are dropped
+
+            [group1]
+            k1=v1
+            k2 = v2
+            [group2]
+            k3=v3
         "})
         .unwrap();
 
-        let group = file.group("group").unwrap();
         assert_eq!(
-            group
-                .get_raw(LocalizedKey {
-                    key: "Name",
-                    lang: "sr",
-                    country: Some("YU"),
-                    modifier: Some("Latn"),
-                })
-                .unwrap(),
-            "localized sr_YU"
+            file.to_string(),
+            indoc! {"
+                # comments are kept, but blank lines are dropped
+                [group1]
+                k1=v1
+                k2=v2
+
+                [group2]
+                k3=v3
+            "}
         );
-        assert_eq!(
-            group
-                .get_raw(LocalizedKey {
-                    key: "Name",
-                    lang: "sr",
-                    country: Some("YU"),
-                    modifier: None,
+    }
+
+    #[test]
+    fn desktop_file_comments() {
+        let contents = indoc! {"
+            # before the first group
+            [group1]
+            # before k1
+            #   spanning two lines
+            k1=v1
+            k2=v2
+
+            # before group2
+            [group2]
+            #before k3
+            k3=v3
+            # at the end
+        "};
+        let file = DesktopFile::parse(contents).unwrap();
+
+        let group1 = file.group("group1").unwrap();
+        assert_eq!(
+            group1.comments().collect::<Vec<_>>(),
+            [" before the first group"]
+        );
+        assert_eq!(
+            group1.comments_for("k1").collect::<Vec<_>>(),
+            [" before k1", "   spanning two lines"]
+        );
+        assert_eq!(group1.comments_for("k2").count(), 0);
+        assert_eq!(
+            file.trailing_comments().collect::<Vec<_>>(),
+            [" at the end"]
+        );
+
+        // comments are written back in place, only the blank line before group2 is normalized
+        assert_eq!(file.to_string(), contents);
+
+        // removing an entry removes its comments
+        let mut file = file;
+        file.group_mut("group2").unwrap().remove("k3");
+        assert_eq!(
+            file.to_string(),
+            indoc! {"
+                # before the first group
+                [group1]
+                # before k1
+                #   spanning two lines
+                k1=v1
+                k2=v2
+
+                # before group2
+                [group2]
+                # at the end
+            "}
+        );
+    }
+
+    #[test]
+    fn desktop_file_merge() {
+        let mut file = DesktopFile::parse(indoc! {"
+            [group1]
+            k1=v1
+            # kept
+            k2=v2
+            [group2]
+            k3=v3
+        "})
+        .unwrap();
+        let other = DesktopFile::parse(indoc! {"
+            [group1]
+            k2=overridden
+            k4=v4
+            [group3]
+            k5=v5
+        "})
+        .unwrap();
+        file.merge(other);
+
+        assert_eq!(
+            file.to_string(),
+            indoc! {"
+                [group1]
+                k1=v1
+                # kept
+                k2=overridden
+                k4=v4
+
+                [group2]
+                k3=v3
+
+                [group3]
+                k5=v5
+            "}
+        );
+    }
+
+    #[test]
+    fn desktop_file_round_trip() {
+        let contents = indoc! {r"
+            [Desktop Entry]
+            Type=Application
+            Name=Text\sEditor
+            Name[de]=Texteditor
+            Keywords=text;editor;semi\;colon;
+            Exec=kate -b %U
+
+            [Desktop Action new-window]
+            Name=New Window
+        "};
+        let file = DesktopFile::parse(contents).unwrap();
+
+        let serialized = file.to_string();
+        let reparsed = DesktopFile::parse(&serialized).unwrap();
+        assert_eq!(file, reparsed);
+
+        let group = reparsed.group("Desktop Entry").unwrap();
+        assert_eq!(
+            group.get::<Vec<String>>("Keywords").unwrap().unwrap(),
+            vec!["text", "editor", "semi;colon"]
+        );
+    }
+
+    #[test]
+    fn desktop_file_escaped_semicolons() {
+        let file = DesktopFile::parse(indoc! {r"
+            [group]
+            k=dog\;cat;bird
+        "})
+        .unwrap();
+        let group = file.group("group").unwrap();
+
+        // `\;` has no meaning in a string...
+        assert_eq!(group.get::<String>("k").unwrap().unwrap(), r"dog\;cat;bird");
+        // ... but is an escaped separator in a list of strings
+        assert_eq!(
+            group.get::<Vec<String>>("k").unwrap().unwrap(),
+            vec!["dog;cat", "bird"]
+        );
+    }
+
+    #[test]
+    fn desktop_file_mutation() {
+        let contents = indoc! {"
+            [group1]
+            k1=v1
+            k2=v2
+        "};
+        let mut file = DesktopFile::parse(contents).unwrap();
+
+        // overwriting an existing key
+        let group1 = file.group_mut("group1").unwrap();
+        assert_eq!(group1.set_raw("k1", "new v1").as_deref(), Some("v1"));
+        assert_eq!(group1.get_raw("k1").unwrap(), "new v1");
+
+        // removing keys
+        assert_eq!(group1.remove("k2").as_deref(), Some("v2"));
+        assert!(group1.get_raw("k2").is_none());
+        assert!(group1.remove("k3").is_none());
+
+        // inserting into a new group, with owned keys and values
+        let group2 = file.insert_group("group2".to_string());
+        assert!(group2
+            .set_raw("k3".to_string(), format!("v{}", 3))
+            .is_none());
+
+        // ... and inserting an existing group doesn't clear it
+        file.insert_group("group1").set_raw("k4", "v4");
+
+        assert!(file.group_mut("group3").is_none());
+        assert_eq!(
+            file.to_string(),
+            indoc! {"
+                [group1]
+                k1=new v1
+                k4=v4
+
+                [group2]
+                k3=v3
+            "}
+        );
+    }
+
+    #[test]
+    fn desktop_file_parse_group() {
+        let s = indoc! {"
+            [Desktop Entry]
+            Name=App
+            Actions=new;
+
+            [Desktop Action new]
+            Name=New
+            Name=Duplicate keys aren't checked in skipped groups
+        "};
+
+        let file = DesktopFile::parse_group(s, "Desktop Entry").unwrap();
+        assert_eq!(
+            file.groups().map(|(name, _)| name).collect::<Vec<_>>(),
+            ["Desktop Entry"]
+        );
+        assert_eq!(
+            file.group("Desktop Entry").unwrap().get_raw("Name"),
+            Some("App")
+        );
+        assert_eq!(file.group("Desktop Action new"), None);
+
+        // a missing group gives an empty file
+        let file = DesktopFile::parse_group(s, "Desktop Action missing").unwrap();
+        assert_eq!(file.groups().count(), 0);
+
+        // but the file as a whole must still be well-formed
+        assert_eq!(
+            DesktopFile::parse_group("[a]\n[b]\n[a]\n", "b"),
+            Err(DesktopFileError::DuplicateGroup("a", 8..12))
+        );
+        assert_eq!(
+            DesktopFile::parse_group("k=v\n[a]\n", "a"),
+            Err(DesktopFileError::EntryOutsideOfGroup("k", 0..4))
+        );
+    }
+
+    #[test]
+    fn desktop_file_group_names() {
+        let file = DesktopFile::parse(indoc! {"
+            [Desktop Entry]
+            [Desktop Action new-window]
+            [A Group]
+        "})
+        .unwrap();
+
+        assert_eq!(
+            file.group_names().collect::<Vec<_>>(),
+            ["Desktop Entry", "Desktop Action new-window", "A Group"]
+        );
+    }
+
+    #[test]
+    fn desktop_file_groups_with_prefix() {
+        let file = DesktopFile::parse(indoc! {"
+            [Desktop Entry]
+            Actions=new-window;new-private-window;
+
+            [Desktop Action new-window]
+            Name=New Window
+
+            [X-Desktop Action]
+
+            [Desktop Action new-private-window]
+            Name=New Private Window
+        "})
+        .unwrap();
+
+        assert_eq!(
+            file.groups_with_prefix("Desktop Action ")
+                .map(|(name, _)| name)
+                .collect::<Vec<_>>(),
+            [
+                "Desktop Action new-window",
+                "Desktop Action new-private-window"
+            ]
+        );
+        assert_eq!(file.groups_with_prefix("Missing").count(), 0);
+    }
+
+    #[test]
+    fn desktop_file_group_ignore_case() {
+        let file = DesktopFile::parse("[desktop entry]\nName=lowercase\n[Other]\n").unwrap();
+        let group = file.group_ignore_case("Desktop Entry").unwrap().unwrap();
+        assert_eq!(group.get_raw("Name"), Some("lowercase"));
+        assert!(file.group_ignore_case("Missing").unwrap().is_none());
+
+        // groups which only differ by case can't be told apart
+        let file = DesktopFile::parse("[Desktop Entry]\n[desktop entry]\n").unwrap();
+        assert_eq!(
+            file.group_ignore_case("DESKTOP ENTRY").unwrap_err(),
+            AmbiguousGroup(vec![
+                "Desktop Entry".to_string(),
+                "desktop entry".to_string()
+            ])
+        );
+    }
+
+    #[test]
+    fn extensions() {
+        let file = DesktopFile::parse(indoc! {"
+            [group]
+            Name=Autostart
+            X-GNOME-Autostart-enabled=false
+            X-Vendor-Name=Vendor
+            X-Vendor-Name[de]=Anbieter
+            NotX-Key=value
+            x-lowercase=value
+        "})
+        .unwrap();
+        let group = file.group("group").unwrap();
+
+        assert_eq!(
+            group.extensions().collect::<Vec<_>>(),
+            [
+                ("X-GNOME-Autostart-enabled", "false"),
+                ("X-Vendor-Name", "Vendor"),
+                ("X-Vendor-Name[de]", "Anbieter"),
+            ]
+        );
+    }
+
+    #[test]
+    fn locales_for() {
+        let file = DesktopFile::parse(indoc! {"
+            [group]
+            Name=Name
+            Name[de]=Name (de)
+            Name[sr_YU@Latn]=Name (sr_YU@Latn)
+            Name[_AT]=Malformed
+            NameSuffix[fr]=Different key
+            Comment[fr]=Different key
+        "})
+        .unwrap();
+        let group = file.group("group").unwrap();
+
+        assert_eq!(
+            group.locales_for("Name"),
+            [
+                LocalizedKey {
+                    key: "Name",
+                    lang: "de",
+                    country: None,
+                    modifier: None,
+                },
+                LocalizedKey {
+                    key: "Name",
+                    lang: "sr",
+                    country: Some("YU"),
+                    modifier: Some("Latn"),
+                },
+            ]
+        );
+        assert_eq!(group.locales_for("GenericName"), []);
+    }
+
+    #[test]
+    fn localized_entries() {
+        let file = DesktopFile::parse(indoc! {"
+            [group]
+            Name=Name
+            Comment=Comment
+            Comment[fr_CA]=Comment (fr_CA)
+            Name[_AT]=Malformed
+        "})
+        .unwrap();
+        let group = file.group("group").unwrap();
+
+        assert_eq!(
+            group.localized_entries().collect::<Vec<_>>(),
+            [
+                ("Name", None, "Name"),
+                ("Comment", None, "Comment"),
+                (
+                    "Comment[fr_CA]",
+                    Some(LocalizedKey {
+                        key: "Comment",
+                        lang: "fr",
+                        country: Some("CA"),
+                        modifier: None,
+                    }),
+                    "Comment (fr_CA)"
+                ),
+                ("Name[_AT]", None, "Malformed"),
+            ]
+        );
+    }
+
+    #[test]
+    fn desktop_file_crlf() {
+        let file = DesktopFile::parse("[g]\r\nk=v\r\n").unwrap();
+        assert_eq!(
+            file.groups().map(|(name, _)| name).collect::<Vec<_>>(),
+            ["g"]
+        );
+        assert_eq!(file.group("g").unwrap().get_raw("k"), Some("v"));
+    }
+
+    #[test]
+    fn desktop_file_lenient() {
+        let file = DesktopFile::parse(indoc! {"
+            [group]
+            k1=1
+            k2=False
+        "})
+        .unwrap();
+        let group = file.group("group").unwrap();
+
+        // lenient parsing accepts legacy and miscapitalised booleans...
+        assert_eq!(group.get_lenient::<bool>("k1"), Some(Ok(true)));
+        assert_eq!(group.get_lenient::<bool>("k2"), Some(Ok(false)));
+        assert_eq!(group.get_lenient::<bool>("k3"), None);
+        // ... but strict parsing doesn't
+        assert!(group.get::<bool>("k1").unwrap().is_err());
+        assert!(group.get::<bool>("k2").unwrap().is_err());
+    }
+
+    #[test]
+    fn desktop_file_localized() {
+        let file = DesktopFile::parse(indoc! {"
+            [group]
+            Name=default value
+            Name[sr_YU]=localized sr_YU
+            Name[sr@Latn]=localized sr@Latn
+            Name[sr]=localized sr
+        "})
+        .unwrap();
+
+        let group = file.group("group").unwrap();
+        assert_eq!(
+            group
+                .get_raw(LocalizedKey {
+                    key: "Name",
+                    lang: "sr",
+                    country: Some("YU"),
+                    modifier: Some("Latn"),
+                })
+                .unwrap(),
+            "localized sr_YU"
+        );
+        assert_eq!(
+            group
+                .get_raw(LocalizedKey {
+                    key: "Name",
+                    lang: "sr",
+                    country: Some("YU"),
+                    modifier: None,
                 })
                 .unwrap(),
             "localized sr_YU"
@@ -357,6 +1652,70 @@ mod tests {
         );
     }
 
+    #[test]
+    fn group_contains_key_len() {
+        let file = DesktopFile::parse(indoc! {"
+            [group]
+            Name=default value
+            Name[de]=localized de
+            Comment=comment
+            [empty]
+        "})
+        .unwrap();
+        let group = file.group("group").unwrap();
+
+        assert_eq!(group.len(), 3);
+        assert!(!group.is_empty());
+        assert!(file.group("empty").unwrap().is_empty());
+
+        assert!(group.contains_key("Name"));
+        assert!(!group.contains_key("Icon"));
+        // localized keys fall back like get_raw
+        let de_at = Locale::parse("de_AT").unwrap();
+        assert!(group.contains_key(de_at.key("Name")));
+        assert!(group.contains_key(de_at.key("Comment")));
+        assert!(!group.contains_key(de_at.key("Icon")));
+        assert!(!group.contains_key("Name[de_AT]"));
+    }
+
+    #[test]
+    fn group_get_raw_exact() {
+        let file = DesktopFile::parse(
+            "[group]
+Name=default value
+Comment[de]=localized de
+",
+        )
+        .unwrap();
+        let group = file.group("group").unwrap();
+        let de = Locale::parse("de").unwrap();
+
+        // there's no translation...
+        assert_eq!(group.get_raw_exact("Name[de]"), None);
+        // ... even though a localized lookup falls back to the default
+        assert_eq!(group.get_raw(de.key("Name")), Some("default value"));
+        assert_eq!(group.get_raw_exact("Name"), Some("default value"));
+        assert_eq!(group.get_raw_exact("Comment[de]"), Some("localized de"));
+    }
+
+    #[test]
+    fn group_get_or() {
+        let file = DesktopFile::parse(indoc! {"
+            [group]
+            Terminal=true
+            Hidden=maybe
+        "})
+        .unwrap();
+        let group = file.group("group").unwrap();
+
+        // absent keys use the default
+        assert_eq!(group.get_or("NoDisplay", false), Ok(false));
+        // present keys are parsed
+        assert_eq!(group.get_or("Terminal", false), Ok(true));
+        // and errors aren't hidden by the default
+        assert!(group.get_or("Hidden", false).is_err());
+    }
+
     #[test]
     fn desktop_file_error_parse() {
         let err = DesktopFile::parse(indoc! {"
@@ -367,13 +1726,27 @@ mod tests {
         assert!(matches!(err, DesktopFileError::Parse(_)));
     }
 
+    #[test]
+    fn desktop_file_error_parse_location() {
+        let err = DesktopFile::parse("[group[name]\nk=v\n").unwrap_err();
+        let DesktopFileError::Parse(parse_err) = &err else {
+            panic!("expected a parse error, got {err:?}");
+        };
+
+        let (line, column) = parse_err.location();
+        assert_eq!(line, 1);
+        assert!(err
+            .to_string()
+            .ends_with(&format!("at line {line} column {column})")));
+    }
+
     #[test]
     fn desktop_file_error_entry_outside_of_group() {
         let err = DesktopFile::parse(indoc! {"
             k=v
         "})
         .unwrap_err();
-        assert_eq!(err, DesktopFileError::EntryOutsideOfGroup("k"));
+        assert_eq!(err, DesktopFileError::EntryOutsideOfGroup("k", 0..4));
     }
 
     #[test]
@@ -387,7 +1760,9 @@ mod tests {
             k3=v3
         "})
         .unwrap_err();
-        assert_eq!(err, DesktopFileError::DuplicateGroup("group1"));
+        assert_eq!(err, DesktopFileError::DuplicateGroup("group1", 30..39));
+        // the span is of the second header
+        assert_eq!(err.span(), Some(30..39));
     }
 
     #[test]
@@ -399,7 +1774,280 @@ mod tests {
             k1=v3
         "})
         .unwrap_err();
-        assert_eq!(err, DesktopFileError::DuplicateKey("k1"));
+        assert_eq!(err, DesktopFileError::DuplicateKey("k1", 21..27));
+    }
+
+    #[test]
+    fn desktop_file_into_owned() {
+        let s = String::from("[group1]\nk1=v1\n[group2]\nk2[de]=v2\n");
+        let file = DesktopFile::parse(&s).unwrap().into_owned();
+        drop(s);
+
+        assert_eq!(file.group("group1").unwrap().get_raw("k1"), Some("v1"));
+        assert_eq!(file.group("group2").unwrap().get_raw("k2[de]"), Some("v2"));
+    }
+
+    #[test]
+    fn desktop_file_without_trailing_newline() {
+        let with_newline = DesktopFile::parse("[g]\nk=v\n").unwrap();
+        assert_eq!(DesktopFile::parse("[g]\nk=v").unwrap(), with_newline);
+        assert_eq!(
+            DesktopFile::from_reader(Cursor::new("[g]\nk=v")).unwrap(),
+            with_newline
+        );
+
+        // comments and group headers can also be last
+        let file = DesktopFile::parse("[g]\nk=v\n# comment").unwrap();
+        assert_eq!(file.trailing_comments, [" comment"]);
+        assert!(DesktopFile::parse("[g]").unwrap().group("g").is_some());
+        // ... but not a partial group header
+        assert!(DesktopFile::parse("[g]\nk=v\n[h").is_err());
+        assert!(DesktopFile::from_reader(Cursor::new("[g]\nk=v\n[h")).is_err());
+    }
+
+    #[test]
+    fn desktop_file_bom() {
+        let s = "\u{FEFF}[Desktop Entry]\nName=Firefox\n";
+
+        let file = DesktopFile::parse(s).unwrap();
+        let group = file.group("Desktop Entry").unwrap();
+        assert_eq!(group.get_raw("Name"), Some("Firefox"));
+        assert_eq!(
+            DesktopFile::from_reader(Cursor::new(s.as_bytes())).unwrap(),
+            file
+        );
+
+        // spans still index into the input, BOM and all
+        let err = DesktopFile::parse("\u{FEFF}k=v\n").unwrap_err();
+        assert_eq!(err, DesktopFileError::EntryOutsideOfGroup("k", 3..7));
+        // only a leading BOM is ignored
+        assert!(DesktopFile::parse("[a]\n\u{FEFF}k=v\n").is_err());
+    }
+
+    #[test]
+    fn desktop_file_from_reader() {
+        let s = indoc! {"
+            # comment
+
+            [group1]
+            k1=v1
+            k2 = v2\r
+            [group2]
+            k3[de]=v3
+        "};
+
+        let file = DesktopFile::from_reader(Cursor::new(s.as_bytes())).unwrap();
+        assert_eq!(file, DesktopFile::parse(s).unwrap());
+        assert_eq!(file.group("group2").unwrap().get_raw("k3[de]"), Some("v3"));
+    }
+
+    #[test]
+    fn desktop_file_from_reader_errors() {
+        let err =
+            DesktopFile::from_reader(Cursor::new("[group1]\nk=v\n[group[name]\n")).unwrap_err();
+        let ReadError::Parse(parse_err) = &err else {
+            panic!("expected a parse error, got {err:?}");
+        };
+        // the location is within the whole file, not just the line
+        assert_eq!(parse_err.location().0, 3);
+
+        let err = DesktopFile::from_reader(Cursor::new("k=v\n")).unwrap_err();
+        assert!(matches!(err, ReadError::EntryOutsideOfGroup(key) if key == "k"));
+
+        let err = DesktopFile::from_reader(Cursor::new("[a]\n[b]\n[a]\n")).unwrap_err();
+        assert!(matches!(err, ReadError::DuplicateGroup(group) if group == "a"));
+
+        let err = DesktopFile::from_reader(Cursor::new("[a]\nk=v1\nk=v2\n")).unwrap_err();
+        assert!(matches!(err, ReadError::DuplicateKey(key) if key == "k"));
+    }
+
+    #[test]
+    fn desktop_file_lenient_duplicate_key() {
+        let s = indoc! {"
+            [group1]
+            k1=v1
+            k2=v2
+            k1=v3
+            k1=v4
+        "};
+
+        // strict parsing still errors
+        assert_eq!(
+            DesktopFile::parse(s).unwrap_err(),
+            DesktopFileError::DuplicateKey("k1", 21..27)
+        );
+
+        let file = DesktopFile::parse_lenient(s).unwrap();
+        let group = file.group("group1").unwrap();
+        assert_eq!(group.get_all("k1"), ["v1", "v3", "v4"]);
+        assert_eq!(group.get_all("k2"), ["v2"]);
+        assert!(group.get_all("k3").is_empty());
+        // the first value is used when only one is wanted
+        assert_eq!(group.get_raw("k1"), Some("v1"));
+
+        assert_eq!(
+            file.to_string(),
+            indoc! {"
+                [group1]
+                k1=v1
+                k1=v3
+                k1=v4
+                k2=v2
+            "}
+        );
+    }
+
+    #[test]
+    fn desktop_file_with_warnings() {
+        let s = indoc! {"
+            [group1]
+            k1=v1
+            k2=v2
+            k1=v3
+            [group2]
+            k3=v4
+            [group1]
+            k2=v5
+            k4=v6
+        "};
+
+        // strict parsing still errors
+        assert_eq!(
+            DesktopFile::parse(s).unwrap_err(),
+            DesktopFileError::DuplicateKey("k1", 21..27)
+        );
+
+        let (file, warnings) = DesktopFile::parse_with_warnings(s).unwrap();
+        assert_eq!(
+            warnings,
+            [
+                Warning::DuplicateKey {
+                    group: "group1",
+                    key: "k1"
+                },
+                Warning::DuplicateGroup { group: "group1" },
+                Warning::DuplicateKey {
+                    group: "group1",
+                    key: "k2"
+                },
+            ]
+        );
+        // the last value wins, and the repeated group is merged into the first
+        assert_eq!(
+            file.to_string(),
+            indoc! {"
+                [group1]
+                k1=v3
+                k2=v5
+                k4=v6
+
+                [group2]
+                k3=v4
+            "}
+        );
+
+        // a file without problems has no warnings
+        let (file, warnings) = DesktopFile::parse_with_warnings("[group1]\nk1=v1\n").unwrap();
+        assert_eq!(file, DesktopFile::parse("[group1]\nk1=v1\n").unwrap());
+        assert!(warnings.is_empty());
+        // and errors which can't be worked around are still errors
+        assert!(DesktopFile::parse_with_warnings("k1=v1\n").is_err());
+    }
+
+    #[test]
+    fn locale_parse() {
+        assert_eq!(
+            Locale::parse("sr_YU@Latn"),
+            Some(Locale {
+                lang: "sr".to_string(),
+                country: Some("YU".to_string()),
+                modifier: Some("Latn".to_string()),
+            })
+        );
+        assert_eq!(
+            Locale::parse("de"),
+            Some(Locale {
+                lang: "de".to_string(),
+                country: None,
+                modifier: None,
+            })
+        );
+        assert_eq!(Locale::parse("de_"), None);
+
+        let locale = Locale::parse("de_AT").unwrap();
+        assert_eq!(
+            locale.key("Name"),
+            LocalizedKey {
+                key: "Name",
+                lang: "de",
+                country: Some("AT"),
+                modifier: None,
+            }
+        );
+    }
+
+    #[test]
+    fn locale_display() {
+        for locale in ["de", "de_AT", "sr@Latn", "sr_YU@Latn"] {
+            assert_eq!(Locale::parse(locale).unwrap().to_string(), locale);
+        }
+    }
+
+    #[test]
+    fn locale_parse_posix() {
+        assert_eq!(
+            Locale::parse_posix("de_AT@euro"),
+            Some(Locale {
+                lang: "de".to_string(),
+                country: Some("AT".to_string()),
+                modifier: Some("euro".to_string()),
+            })
+        );
+        // the encoding is ignored
+        assert_eq!(
+            Locale::parse_posix("en_US.UTF-8"),
+            Some(Locale {
+                lang: "en".to_string(),
+                country: Some("US".to_string()),
+                modifier: None,
+            })
+        );
+        assert_eq!(
+            Locale::parse_posix("sr_RS.UTF-8@latin"),
+            Some(Locale {
+                lang: "sr".to_string(),
+                country: Some("RS".to_string()),
+                modifier: Some("latin".to_string()),
+            })
+        );
+        // no localization
+        assert_eq!(Locale::parse_posix("C"), None);
+        assert_eq!(Locale::parse_posix("C.UTF-8"), None);
+        assert_eq!(Locale::parse_posix("POSIX"), None);
+    }
+
+    #[test]
+    fn desktop_file_localized_encoding() {
+        let file = DesktopFile::parse(indoc! {"
+            [group]
+            Name=default value
+            Name[sr_YU]=localized sr_YU
+            Name[de_DE.UTF-8]=localized de_DE
+            Name[fr.ISO-8859-1@euro]=localized fr@euro
+        "})
+        .unwrap();
+        let group = file.group("group").unwrap();
+
+        // the encoding of the requested locale is ignored...
+        let sr = Locale::parse("sr_YU.UTF-8").unwrap();
+        assert_eq!(group.get_raw(sr.key("Name")), Some("localized sr_YU"));
+        // ... as is the encoding of the key in the file
+        let de = Locale::parse("de_DE").unwrap();
+        assert_eq!(group.get_raw(de.key("Name")), Some("localized de_DE"));
+        let fr = Locale::parse("fr_FR@euro").unwrap();
+        assert_eq!(group.get_raw(fr.key("Name")), Some("localized fr@euro"));
+        let de_at = Locale::parse("de_AT").unwrap();
+        assert_eq!(group.get_raw(de_at.key("Name")), Some("default value"));
     }
 
     #[test]
@@ -452,4 +2100,169 @@ mod tests {
         };
         assert_eq!(locale_key.matches(), vec!["key[de]", "key",]);
     }
+
+    #[test]
+    fn path_buf() {
+        assert_eq!(
+            PathBuf::from_raw("/opt/My\\sApp"),
+            Ok(PathBuf::from("/opt/My App"))
+        );
+        assert_eq!(PathBuf::from_raw("relative"), Ok(PathBuf::from("relative")));
+    }
+
+    #[test]
+    fn path_bufs() {
+        assert_eq!(
+            Vec::<PathBuf>::from_raw("/a;/b\\;c;"),
+            Ok(vec![PathBuf::from("/a"), PathBuf::from("/b;c")])
+        );
+    }
+
+    #[test]
+    fn char() {
+        assert_eq!(char::from_raw("a"), Ok('a'));
+        // escapes are unescaped first
+        assert_eq!(char::from_raw("\\t"), Ok('\t'));
+        // there must be exactly one character
+        assert!(char::from_raw("").is_err());
+        assert!(char::from_raw("ab").is_err());
+    }
+
+    #[test]
+    fn sep_list() {
+        let file = DesktopFile::parse(indoc! {r"
+            [Desktop Entry]
+            Categories=Network,WebBrowser\,Viewer,
+            Keywords=web;internet;
+            Path=/opt/a,/opt/b
+        "})
+        .unwrap();
+        let group = file.group("Desktop Entry").unwrap();
+
+        assert_eq!(
+            group.get::<SepList<',', String>>("Categories"),
+            Some(Ok(SepList(vec![
+                "Network".to_string(),
+                "WebBrowser,Viewer".to_string()
+            ])))
+        );
+        // lists separated by `;` are the same as a `Vec`
+        assert_eq!(
+            group.get::<SepList<';'>>("Keywords").unwrap().unwrap().0,
+            group.get::<Vec<String>>("Keywords").unwrap().unwrap()
+        );
+        // ... which doesn't split on commas
+        assert_eq!(
+            group.get::<Vec<String>>("Path"),
+            Some(Ok(vec!["/opt/a,/opt/b".to_string()]))
+        );
+        assert_eq!(
+            group.get::<SepList<',', PathBuf>>("Path"),
+            Some(Ok(SepList(vec!["/opt/a".into(), "/opt/b".into()])))
+        );
+    }
+
+    #[test]
+    fn integers() {
+        let file = DesktopFile::parse("[Group]\nX-Timeout=5000\nX-Offset=-5\n").unwrap();
+        let group = file.group("Group").unwrap();
+
+        assert_eq!(group.get::<u64>("X-Timeout"), Some(Ok(5000)));
+        assert_eq!(group.get::<i64>("X-Offset"), Some(Ok(-5)));
+        // negative values aren't unsigned
+        assert!(group.get::<u64>("X-Offset").unwrap().is_err());
+    }
+
+    #[test]
+    fn icon_string() {
+        assert_eq!(
+            IconString::from_raw("/usr/share/icons/foo.png"),
+            Ok(IconString::Path("/usr/share/icons/foo.png".into()))
+        );
+        assert_eq!(
+            IconString::from_raw("firefox"),
+            Ok(IconString::Name("firefox".to_string()))
+        );
+        // relative paths are only detected by their extension
+        assert_eq!(
+            IconString::from_raw("icons/foo.SVG"),
+            Ok(IconString::Path("icons/foo.SVG".into()))
+        );
+        // dots are allowed in icon names
+        assert_eq!(
+            IconString::from_raw("org.mozilla.firefox"),
+            Ok(IconString::Name("org.mozilla.firefox".to_string()))
+        );
+        // escapes are unescaped
+        assert_eq!(
+            IconString::from_raw("/opt/My\\sApp/icon.png"),
+            Ok(IconString::Path("/opt/My App/icon.png".into()))
+        );
+    }
+
+    #[test]
+    fn key_with_locale() {
+        assert_eq!(parse_key_with_locale("Name"), ("Name", None));
+        assert_eq!(
+            parse_key_with_locale("Name[de]"),
+            (
+                "Name",
+                Some(LocalizedKey {
+                    key: "Name",
+                    lang: "de",
+                    country: None,
+                    modifier: None,
+                })
+            )
+        );
+        assert_eq!(
+            parse_key_with_locale("Name[sr_YU@Latn]"),
+            (
+                "Name",
+                Some(LocalizedKey {
+                    key: "Name",
+                    lang: "sr",
+                    country: Some("YU"),
+                    modifier: Some("Latn"),
+                })
+            )
+        );
+        // malformed locales are left as part of the key
+        assert_eq!(parse_key_with_locale("Name[de"), ("Name[de", None));
+    }
+
+    #[test]
+    fn desktop_file_id_nested() {
+        let base = Path::new("/usr/share/applications");
+
+        assert_eq!(
+            desktop_file_id(base, &base.join("firefox.desktop")).as_deref(),
+            Some("firefox")
+        );
+        assert_eq!(
+            desktop_file_id(base, &base.join("kde/foo.desktop")).as_deref(),
+            Some("kde-foo")
+        );
+        assert_eq!(
+            desktop_file_id(base, &base.join("a/b/org.example.App.desktop")).as_deref(),
+            Some("a-b-org.example.App")
+        );
+        // base directories may have a trailing slash
+        assert_eq!(
+            desktop_file_id(
+                Path::new("/usr/share/applications/"),
+                &base.join("kde/foo.desktop")
+            )
+            .as_deref(),
+            Some("kde-foo")
+        );
+
+        // not within the base directory
+        assert!(desktop_file_id(base, Path::new("/opt/foo.desktop")).is_none());
+        // not a desktop file
+        assert!(desktop_file_id(base, &base.join("foo.txt")).is_none());
+        assert!(desktop_file_id(base, &base.join(".desktop")).is_none());
+        // the base directory itself
+        assert!(desktop_file_id(base, base).is_none());
+    }
 }
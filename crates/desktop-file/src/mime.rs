@@ -0,0 +1,93 @@
+//! Finds the applications which handle a MIME type, from the `MimeType` key of their desktop
+//! entries.
+use indexmap::IndexMap;
+
+use crate::desktop_entry::DesktopEntryApplication;
+
+/// Maps MIME types to the IDs of the applications which list them in `MimeType`.
+#[derive(Debug, Clone, Default)]
+pub struct MimeIndex<'a> {
+    handlers: IndexMap<String, Vec<&'a str>>,
+}
+
+impl<'a> MimeIndex<'a> {
+    /// Builds an index from pairs of application IDs (e.g. from [`crate::desktop_file_id`]) and
+    /// their applications. Where several applications handle a MIME type, they're listed in the
+    /// order they're given.
+    pub fn new(applications: &[(&'a str, &DesktopEntryApplication)]) -> Self {
+        let mut handlers: IndexMap<String, Vec<&'a str>> = IndexMap::new();
+        for (app_id, application) in applications {
+            for mime in application.mime_type.iter().flatten() {
+                let mime = normalize(mime);
+                if mime.is_empty() {
+                    continue;
+                }
+
+                let app_ids = handlers.entry(mime).or_default();
+                if !app_ids.contains(app_id) {
+                    app_ids.push(app_id);
+                }
+            }
+        }
+
+        Self { handlers }
+    }
+
+    /// Returns the IDs of the applications which handle `mime`, ignoring case.
+    pub fn handlers_for(&self, mime: &str) -> Vec<&'a str> {
+        self.handlers
+            .get(&normalize(mime))
+            .cloned()
+            .unwrap_or_default()
+    }
+}
+
+/// MIME types are case-insensitive, so they're compared in lowercase.
+fn normalize(mime: &str) -> String {
+    mime.trim().to_ascii_lowercase()
+}
+
+#[cfg(test)]
+mod tests {
+    use indoc::indoc;
+
+    use super::*;
+    use crate::desktop_entry::{DesktopEntry, DesktopEntryType};
+    use crate::DesktopFile;
+
+    fn application(contents: &str) -> DesktopEntryApplication {
+        let file = DesktopFile::parse(contents).unwrap();
+        let desktop_entry = DesktopEntry::try_from_file(&file).unwrap();
+        let DesktopEntryType::Application(application) = desktop_entry.for_type else {
+            panic!("expected an application");
+        };
+
+        application
+    }
+
+    #[test]
+    fn handlers_for_shared_type() {
+        let firefox = application(indoc! {"
+            [Desktop Entry]
+            Type=Application
+            Name=Firefox
+            Exec=firefox %u
+            MimeType=text/html;x-scheme-handler/https;
+        "});
+        let editor = application(indoc! {"
+            [Desktop Entry]
+            Type=Application
+            Name=Text Editor
+            Exec=editor %F
+            MimeType=text/plain;Text/HTML;
+        "});
+        let index = MimeIndex::new(&[("firefox.desktop", &firefox), ("editor.desktop", &editor)]);
+
+        assert_eq!(
+            index.handlers_for("text/html"),
+            ["firefox.desktop", "editor.desktop"]
+        );
+        assert_eq!(index.handlers_for("TEXT/PLAIN"), ["editor.desktop"]);
+        assert_eq!(index.handlers_for("image/png"), [] as [&str; 0]);
+    }
+}
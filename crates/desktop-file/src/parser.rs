@@ -1,3 +1,5 @@
+use std::ops::Range;
+
 #[derive(Debug)]
 pub enum Line<'input> {
     Blank,
@@ -8,28 +10,87 @@ pub enum Line<'input> {
 
 peg::parser! {
     pub grammar file_parser() for str {
-        pub(super) rule line_blank() = "\n";
+        /// Lines end with a linefeed, optionally preceded by a carriage return which is not part of
+        /// the line's contents.
+        rule eol() -> () = "\r"? "\n" {};
+        /// The end of the input, which also ends the last line if it has no linefeed.
+        rule eof() -> () = ![_] {};
+        /// Any character which doesn't begin the end of the line.
+        rule line_char() = !eol() [_];
+
+        pub(super) rule line_blank() = eol();
 
-        pub(super) rule line_comment() -> &'input str = "#" c:$([^'\n']*) "\n" { c };
+        rule comment() -> &'input str = "#" c:$(line_char()*) { c };
+        pub(super) rule line_comment() -> &'input str = c:comment() eol() { c };
 
-        pub(super) rule line_group_header() -> &'input str = "[" gn:$([^'[' | ']']+) "]\n" { gn };
+        rule group_header() -> &'input str = "[" gn:$([^'[' | ']']+) "]" { gn };
+        pub(super) rule line_group_header() -> &'input str = gn:group_header() eol() { gn };
 
-        rule locale() = "[" ['A'..='Z' | 'a'..='z' | '0'..='9' | '-' | '_' | '@']* "]";
+        /// A key has at most one locale, which must be non-empty.
+        rule locale() = "[" ['A'..='Z' | 'a'..='z' | '0'..='9' | '-' | '_' | '.' | '@']+ "]";
         rule key() -> &'input str = $(['A'..='Z' | 'a'..='z' | '0'..='9' | '-']+ locale()?);
-        rule value() -> &'input str = $([^'\n']*);
-        pub(super) rule line_entry() -> (&'input str, &'input str)
-            = k:key() " "* "=" " "* v:value() "\n" { (k, v) };
+        rule value() -> &'input str = $(line_char()*);
+        rule entry() -> (&'input str, &'input str) = k:key() " "* "=" " "* v:value() { (k, v) };
+        pub(super) rule line_entry() -> (&'input str, &'input str) = kv:entry() eol() { kv };
+
+        /// A comment, group header or entry, ended by `end`.
+        rule line_ending_with(end: rule<()>) -> Line<'input>
+            = c:comment() end() { Line::Comment(c) }
+            / gn:group_header() end() { Line::GroupHeader(gn) }
+            / kv:entry() end() { let (k, v) = kv; Line::Entry(k, v) };
 
-        pub(super) rule line() -> Line<'input>
+        pub rule line() -> Line<'input>
             = line_blank() { Line::Blank }
-            / c:line_comment() { Line::Comment(c) }
-            / gn:line_group_header() { Line::GroupHeader(gn)}
-            / kv:line_entry() { let (k, v) = kv; Line::Entry(k, v) };
+            / line_ending_with(<eol()>);
+
+        /// The last line of the input, which doesn't need to end with a linefeed. It can't be
+        /// blank, as there would be nothing to parse.
+        pub rule last_line() -> Line<'input> = line_ending_with(<eof()>);
+
+        rule spanned<T>(r: rule<T>) -> (T, Range<usize>)
+            = start:position!() t:r() end:position!() { (t, start..end) };
 
-        pub rule file() -> Vec<Line<'input>> = line()*;
+        /// Each line, with the byte range it spans in the input (including its line ending).
+        pub rule file() -> Vec<(Line<'input>, Range<usize>)>
+            = lines:spanned(<line()>)* last:spanned(<last_line()>)? {
+                let mut lines = lines;
+                lines.extend(last);
+                lines
+            };
     }
 }
 
+peg::parser! {
+    pub grammar locale_parser() for str {
+        rule component() -> &'input str = $(['A'..='Z' | 'a'..='z' | '0'..='9' | '-']+);
+
+        /// A locale string of the form `lang_COUNTRY.ENCODING@MODIFIER`, where the `_COUNTRY`,
+        /// `.ENCODING` and `@MODIFIER` parts are optional. The encoding is ignored.
+        pub rule locale() -> (&'input str, Option<&'input str>, Option<&'input str>)
+            = lang:component()
+              country:("_" c:component() { c })?
+              ("." component())?
+              modifier:("@" m:component() { m })?
+            { (lang, country, modifier) };
+
+        /// A key with a locale suffix, e.g. `Name[sr_YU@Latn]`.
+        pub rule localized_key()
+            -> (&'input str, (&'input str, Option<&'input str>, Option<&'input str>))
+            = key:$([^'[' | ']']+) "[" l:locale() "]" { (key, l) };
+    }
+}
+
+/// Optional termination of a list with its separator results in an empty string as the final entry.
+/// Removes it if it's not the only entry.
+fn without_terminator(mut ss: Vec<String>) -> Vec<String> {
+    let len = ss.len();
+    if len > 1 && ss[len - 1].is_empty() {
+        ss.pop();
+    }
+
+    ss
+}
+
 #[derive(Clone, Copy)]
 enum Semicolons {
     Escaped,
@@ -38,57 +99,93 @@ enum Semicolons {
 
 peg::parser! {
     pub grammar value_parser() for str {
-        rule string_escape(semicolons: Semicolons) -> char
+        rule string_escape(semicolons: Semicolons) -> &'input str
             = "\\" c:(
-                "s" { ' ' }
-                / "n" { '\n' }
-                / "t" { '\t' }
-                / "r" { '\r' }
-                / ";" {?
+                "s" { " " }
+                / "n" { "\n" }
+                / "t" { "\t" }
+                / "r" { "\r" }
+                / ";" {
                     match semicolons {
-                        Semicolons::Escaped => Ok(';'),
-                        Semicolons::Raw => Err("")
+                        Semicolons::Escaped => ";",
+                        // not an escape sequence, so keep the backslash
+                        Semicolons::Raw => "\\;",
                     }
                 }
-                / "\\" { '\\' }
+                / "\\" { "\\" }
             ) { c };
-        rule string_char(semicolons: Semicolons) -> char
-            = [^';' | '\\']
+        rule string_char(semicolons: Semicolons) -> &'input str
+            = $([^';' | '\\'])
             / ";" {?
                 match semicolons {
                     Semicolons::Escaped => Err(""),
-                    Semicolons::Raw => Ok(';')
+                    Semicolons::Raw => Ok(";")
                 }
             };
 
         rule string_internal(semicolons: Semicolons) -> String
-            = s:(string_escape(semicolons) / string_char(semicolons))* {
-                s.iter().collect::<String>()
-            };
+            = s:(string_escape(semicolons) / string_char(semicolons))* { s.concat() };
         rule string_raw_semicolons() -> String = string_internal(Semicolons::Raw);
         rule string_escaped_semicolons() -> String = string_internal(Semicolons::Escaped);
 
         pub rule string() -> String = string_raw_semicolons();
-        pub rule strings() -> Vec<String> = ss:(string_escaped_semicolons() ** ";") {
-            let mut ss = ss;
+        pub rule strings() -> Vec<String>
+            = ss:(string_escaped_semicolons() ** ";") { without_terminator(ss) };
 
-            // Optional termination with `;` results in an empty string as the final entry. Remove
-            // it if it's not the only entry.
-            let len = ss.len();
-            if len > 1 && ss[len - 1].is_empty() {
-                ss.pop();
-            }
+        rule separated_escape(separator: char) -> &'input str
+            = "\\" c:$([c if c == separator]) { c }
+            / string_escape(Semicolons::Raw);
+        rule separated_char(separator: char) -> &'input str
+            = $([c if c != separator && c != '\\']);
+        rule separated_string(separator: char) -> String
+            = s:(separated_escape(separator) / separated_char(separator))* { s.concat() };
 
-            ss
-        };
+        /// Like `strings`, but separated by `separator`, which can be escaped with a backslash.
+        pub rule separated_strings(separator: char) -> Vec<String>
+            = ss:(separated_string(separator) ** [c if c == separator]) { without_terminator(ss) };
+
+        /// A `string` of exactly one character, after unescaping.
+        pub rule character() -> char
+            = s:string() {?
+                let mut chars = s.chars();
+                match (chars.next(), chars.next()) {
+                    (Some(c), None) => Ok(c),
+                    _ => Err("a single character"),
+                }
+            };
 
         pub rule boolean() -> bool = "true" { true } / "false" { false };
+        /// Also accepts `0` and `1` (as used before version 1.0 of the specification) and any
+        /// capitalisation of `true` and `false`.
+        pub rule boolean_lenient() -> bool
+            = b:$([_]*) {?
+                match b.to_ascii_lowercase().as_str() {
+                    "true" | "1" => Ok(true),
+                    "false" | "0" => Ok(false),
+                    _ => Err("boolean"),
+                }
+            };
+
+        rule digits() = ['0'..='9']+;
+        pub rule numeric() -> f64
+            = n:$(
+                ['+' | '-']?
+                (digits() ("." digits()?)? / "." digits())
+                (['e' | 'E'] ['+' | '-']? digits())?
+            ) {? n.parse().or(Err("numeric")) };
+
+        /// Integers aren't a type in the specification, but are often used by extension keys.
+        pub rule integer() -> i64
+            = n:$(['+' | '-']? digits()) {? n.parse().or(Err("integer")) };
+        pub rule unsigned_integer() -> u64
+            = n:$(digits()) {? n.parse().or(Err("unsigned integer")) };
     }
 }
 
 #[cfg(test)]
 mod file_tests {
     use super::file_parser::*;
+    use super::Line;
     use crate::{assert_errors, assert_parses};
 
     #[test]
@@ -100,6 +197,9 @@ mod file_tests {
         assert_errors!(line_blank("\t\n"));
         // Any line must end with a linefeed
         assert_errors!(line_blank(""));
+        // ... which can be preceded by a carriage return
+        assert_parses!(line_blank("\r\n"), ());
+        assert_errors!(line_blank("\r"));
     }
 
     #[test]
@@ -113,6 +213,9 @@ mod file_tests {
         );
         // Any line must end with a linefeed
         assert_errors!(line_comment("#"));
+        // ... which can be preceded by a carriage return, which isn't part of the comment
+        assert_parses!(line_comment("# comment\r\n"), " comment");
+        assert_parses!(line_comment("# com\rment\r\n"), " com\rment");
     }
 
     #[test]
@@ -124,6 +227,8 @@ mod file_tests {
         assert_errors!(line_group_header("[group]name]\n"));
         // Any line must end with a linefeed
         assert_errors!(line_group_header("[groupname]"));
+        // ... which can be preceded by a carriage return
+        assert_parses!(line_group_header("[groupname]\r\n"), "groupname");
     }
 
     #[test]
@@ -141,11 +246,20 @@ mod file_tests {
             line_entry("key[en_AU@Latn]=value\n"),
             Ok(("key[en_AU@Latn]", "value"))
         );
+        // ... optionally with an encoding
+        assert_eq!(
+            line_entry("key[en_AU.UTF-8]=value\n"),
+            Ok(("key[en_AU.UTF-8]", "value"))
+        );
         // ... which must be at the end of the key
         assert_errors!(line_entry("ke[locale]y=value\n"));
         // ... and cannot contain '[' or ']'
         assert_errors!(line_entry("key[loc[ale]=value\n"));
         assert_errors!(line_entry("key[loc]ale]=value\n"));
+        // ... and must be non-empty
+        assert_errors!(line_entry("key[]=value\n"));
+        // Keys can only have one locale
+        assert_errors!(line_entry("key[de][fr]=value\n"));
         // An '=' must be present
         assert_errors!(line_entry("key\n"));
         // Keys must be non-empty (TODO: is this true?)
@@ -154,6 +268,81 @@ mod file_tests {
         assert_parses!(line_entry("key=\n"), ("key", ""));
         // Any line must end with a linefeed
         assert_errors!(line_entry("key=value"));
+        // ... which can be preceded by a carriage return, which isn't part of the value
+        assert_eq!(line_entry("key=value\r\n"), Ok(("key", "value")));
+        assert_eq!(line_entry("key=val\rue\r\n"), Ok(("key", "val\rue")));
+    }
+
+    #[test]
+    fn parse_last_line() {
+        // The last line doesn't need a linefeed...
+        assert!(matches!(
+            last_line("key=value"),
+            Ok(Line::Entry("key", "value"))
+        ));
+        assert!(matches!(
+            last_line("# comment"),
+            Ok(Line::Comment(" comment"))
+        ));
+        assert!(matches!(
+            last_line("[group]"),
+            Ok(Line::GroupHeader("group"))
+        ));
+        // ... but must still be a whole line
+        assert_errors!(last_line("[group"));
+        assert_errors!(last_line("key"));
+        assert_errors!(last_line(""));
+    }
+
+    #[test]
+    fn parse_file_without_trailing_newline() {
+        let lines = file("[g]\nk=v").unwrap();
+        assert_eq!(lines.len(), 2);
+        assert!(matches!(lines[1], (Line::Entry("k", "v"), ref span) if *span == (4..7)));
+        assert_errors!(file("[g]\n[h"));
+    }
+}
+
+#[cfg(test)]
+mod locale_tests {
+    use super::locale_parser::*;
+    use crate::{assert_errors, assert_parses};
+
+    #[test]
+    fn parse_locale() {
+        // Locales always have a language
+        assert_parses!(locale("de"), ("de", None, None));
+        // ... and optionally a country and/or modifier
+        assert_parses!(locale("de_AT"), ("de", Some("AT"), None));
+        assert_parses!(locale("sr@Latn"), ("sr", None, Some("Latn")));
+        assert_parses!(locale("sr_YU@Latn"), ("sr", Some("YU"), Some("Latn")));
+        // ... in that order
+        assert_errors!(locale("sr@Latn_YU"));
+        // An encoding is allowed, but ignored
+        assert_parses!(locale("en_US.UTF-8"), ("en", Some("US"), None));
+        assert_parses!(locale("sr_YU.UTF-8@Latn"), ("sr", Some("YU"), Some("Latn")));
+        assert_errors!(locale("en_US."));
+        assert_errors!(locale("en@Latn.UTF-8"));
+        // Each part must be non-empty
+        assert_errors!(locale(""));
+        assert_errors!(locale("_AT"));
+        assert_errors!(locale("de_"));
+        assert_errors!(locale("de@"));
+    }
+
+    #[test]
+    fn parse_localized_key() {
+        assert_parses!(localized_key("Name[de]"), ("Name", ("de", None, None)));
+        assert_parses!(
+            localized_key("Name[sr_YU@Latn]"),
+            ("Name", ("sr", Some("YU"), Some("Latn")))
+        );
+        // The locale must be present, valid and closed
+        assert_errors!(localized_key("Name"));
+        assert_errors!(localized_key("Name[]"));
+        assert_errors!(localized_key("Name[de"));
+        assert_errors!(localized_key("Name[de]x"));
+        assert_errors!(localized_key("[de]"));
     }
 }
 
@@ -181,6 +370,12 @@ mod value_tests {
         );
         // But invalid escape sequences are invalid
         assert_errors!(string(r"\q"));
+        // Semicolons have no special meaning in strings
+        assert_parses!(string(r"dog;cat"), "dog;cat".to_string());
+        // ... so `\;` is not an escape sequence, and is kept as-is
+        assert_parses!(string(r"dog\;cat"), r"dog\;cat".to_string());
+        // ... giving the same result as an escaped backslash followed by a semicolon
+        assert_parses!(string(r"dog\\;cat"), r"dog\;cat".to_string());
     }
 
     #[test]
@@ -230,6 +425,50 @@ mod value_tests {
         );
     }
 
+    #[test]
+    fn parse_separated_strings() {
+        // Separated like `strings`, but by the given separator...
+        assert_parses!(
+            separated_strings(r"dog,cat,", ','),
+            vec!["dog".to_string(), "cat".to_string()]
+        );
+        assert_parses!(
+            separated_strings(r"dog;cat;", ';'),
+            vec!["dog".to_string(), "cat".to_string()]
+        );
+        assert_parses!(separated_strings(r"", ','), vec!["".to_string()]);
+        // ... which can be escaped
+        assert_parses!(
+            separated_strings(r"dog\,cat,bird", ','),
+            vec!["dog,cat".to_string(), "bird".to_string()]
+        );
+        assert_parses!(
+            separated_strings(r"dog\;cat;bird", ';'),
+            vec!["dog;cat".to_string(), "bird".to_string()]
+        );
+        // Other separators aren't special
+        assert_parses!(
+            separated_strings(r"dog;cat,bird", ','),
+            vec!["dog;cat".to_string(), "bird".to_string()]
+        );
+        assert_parses!(
+            separated_strings(r"dog\;cat", ','),
+            vec![r"dog\;cat".to_string()]
+        );
+    }
+
+    #[test]
+    fn parse_character() {
+        // Characters are a single character...
+        assert_parses!(character("a"), 'a');
+        // ... after unescaping
+        assert_parses!(character(r"\s"), ' ');
+        // Anything else isn't a character
+        assert_errors!(character(""));
+        assert_errors!(character("ab"));
+        assert_errors!(character(r"\s\s"));
+    }
+
     #[test]
     fn parse_boolean() {
         // Booleans are either true or false
@@ -238,4 +477,75 @@ mod value_tests {
         // Anything else isn't a boolean
         assert_errors!(boolean("blorp"));
     }
+
+    #[test]
+    fn parse_boolean_lenient() {
+        // Lenient booleans are also true or false...
+        assert_parses!(boolean_lenient("true"), true);
+        assert_parses!(boolean_lenient("false"), false);
+        // ... in any case
+        assert_parses!(boolean_lenient("True"), true);
+        assert_parses!(boolean_lenient("FALSE"), false);
+        // ... or 1 or 0
+        assert_parses!(boolean_lenient("1"), true);
+        assert_parses!(boolean_lenient("0"), false);
+        // But anything else still isn't a boolean
+        assert_errors!(boolean_lenient(""));
+        assert_errors!(boolean_lenient("yes"));
+        assert_errors!(boolean_lenient("10"));
+        assert_errors!(boolean_lenient("true "));
+    }
+
+    #[test]
+    #[allow(clippy::approx_constant)]
+    fn parse_numeric() {
+        // Numerics can be integers...
+        assert_parses!(numeric("0"), 0.0);
+        assert_parses!(numeric("42"), 42.0);
+        // ... have a sign, decimal point and fractional part...
+        assert_parses!(numeric("-3.14"), -3.14);
+        assert_parses!(numeric("+2.5"), 2.5);
+        assert_parses!(numeric("1."), 1.0);
+        assert_parses!(numeric(".5"), 0.5);
+        // ... and an exponent
+        assert_parses!(numeric("1e10"), 1e10);
+        assert_parses!(numeric("-1.5E-3"), -1.5e-3);
+        // Numerics can't be empty
+        assert_errors!(numeric(""));
+        assert_errors!(numeric("-"));
+        assert_errors!(numeric("."));
+        // ... or contain anything else
+        assert_errors!(numeric("NaNxyz"));
+        assert_errors!(numeric("1.5abc"));
+        assert_errors!(numeric("1e"));
+    }
+
+    #[test]
+    fn parse_integer() {
+        assert_parses!(integer("5000"), 5000);
+        assert_parses!(integer("-42"), -42);
+        assert_parses!(integer("+7"), 7);
+        assert_parses!(integer("-9223372036854775808"), i64::MIN);
+        // Integers must be in range...
+        assert_errors!(integer("9223372036854775808"));
+        // ... and only contain digits
+        assert_errors!(integer(""));
+        assert_errors!(integer("-"));
+        assert_errors!(integer("1.5"));
+        assert_errors!(integer("1e3"));
+        assert_errors!(integer("12abc"));
+    }
+
+    #[test]
+    fn parse_unsigned_integer() {
+        assert_parses!(unsigned_integer("5000"), 5000);
+        assert_parses!(unsigned_integer("18446744073709551615"), u64::MAX);
+        // Unsigned integers can't have a sign...
+        assert_errors!(unsigned_integer("-1"));
+        assert_errors!(unsigned_integer("+1"));
+        // ... or overflow
+        assert_errors!(unsigned_integer("18446744073709551616"));
+        assert_errors!(unsigned_integer(""));
+        assert_errors!(unsigned_integer("0x10"));
+    }
 }
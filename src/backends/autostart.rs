@@ -0,0 +1,328 @@
+use std::cell::Cell;
+use std::collections::HashMap;
+use std::env;
+use std::ffi::OsString;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use color_eyre::eyre::{eyre, Context, Result};
+use desktop_file::desktop_entry::DesktopEntry;
+use desktop_file::DesktopFile;
+use eframe::egui;
+use log::{error, info, trace, warn};
+use serde::Deserialize;
+
+use crate::backends::{xdg, Backend, Entries, NewBackend};
+use crate::toffee::EntryId;
+
+/// Lists the entries from the autostart directories, toggling an entry's enabled state when it's
+/// selected.
+///
+/// Entries in the user's autostart directory override entries of the same name in the system
+/// autostart directories. Toggling an entry always writes the change to the user's autostart
+/// directory.
+pub struct Autostart {
+    entries: Vec<Entry>,
+    user_directory: PathBuf,
+}
+
+#[derive(Deserialize)]
+pub struct Config {}
+
+impl NewBackend for Autostart {
+    type Config = Config;
+
+    fn new(_cc: &eframe::CreationContext<'_>, _config: Self::Config) -> Result<Self> {
+        let system_directories =
+            xdg("XDG_CONFIG_DIRS", &["/etc/xdg"], "autostart").unwrap_or_else(|err| {
+                warn!("failed to read system autostart directories - {}", err);
+                vec![]
+            });
+        let user_directory = env::var("HOME")
+            .wrap_err("$HOME should be set")
+            .and_then(|home| {
+                let default = PathBuf::from(home).join(".config");
+                xdg("XDG_CONFIG_HOME", &[default], "autostart")
+            })
+            .wrap_err("failed to find the user autostart directory")?
+            .swap_remove(0);
+
+        // later directories take precedence, so go from least to most important
+        let directories = system_directories
+            .into_iter()
+            .rev()
+            .chain([user_directory.clone()]);
+
+        Ok(Self {
+            entries: Self::read_directories(directories),
+            user_directory,
+        })
+    }
+}
+
+impl<'entry> Backend<'entry> for Autostart {
+    type Entry = &'entry Entry;
+
+    fn entries(&'entry self, query: &str) -> Result<Entries<Self::Entry>> {
+        let query = query.to_lowercase();
+        let entries = self
+            .entries
+            .iter()
+            .filter(|entry| entry.name.to_lowercase().contains(&query))
+            .collect::<Vec<_>>();
+
+        Ok(Entries::with_counter(entries, self.entries.len()))
+    }
+
+    fn entry_contents(&self, ui: &mut egui::Ui, entry: Self::Entry) {
+        ui.horizontal(|ui| {
+            ui.label(if entry.enabled.get() { "✔" } else { "✖" });
+            ui.label(&entry.name);
+        });
+    }
+
+    fn on_selected(&self, entry: Self::Entry) {
+        let enabled = !entry.enabled.get();
+        match entry.write_override(&self.user_directory, enabled) {
+            Ok(path) => {
+                info!("set enabled={} in {:?}", enabled, path);
+                entry.enabled.set(enabled);
+            }
+            Err(err) => {
+                error!("toggle failed - {}", err);
+            }
+        }
+    }
+}
+
+impl Autostart {
+    /// Reads the entries in each of `directories`, which are in increasing order of precedence, so
+    /// an entry overrides entries with the same file name in earlier directories. Entries are
+    /// sorted by name.
+    fn read_directories(directories: impl IntoIterator<Item = PathBuf>) -> Vec<Entry> {
+        let mut entries = HashMap::new();
+        for directory in directories {
+            match Self::read_entries(&directory) {
+                Ok(directory_entries) => entries.extend(
+                    directory_entries
+                        .into_iter()
+                        .map(|entry| (entry.file_name.clone(), entry)),
+                ),
+                Err(err) => warn!("failed to read entries - {}", err),
+            }
+        }
+
+        let mut entries = entries.into_values().collect::<Vec<_>>();
+        entries.sort_by(|a, b| a.name.cmp(&b.name));
+        entries
+    }
+
+    fn read_entries(path: &Path) -> Result<Vec<Entry>, String> {
+        trace!("reading entries from {:?}", path);
+
+        let dir = fs::read_dir(path)
+            .map_err(|err| format!("couldn't read directory {:?} - {}", path, err))?;
+
+        let entries = dir
+            .flat_map(|dir_entry| match dir_entry {
+                Ok(dir_entry) => Some(dir_entry.path()),
+                Err(err) => {
+                    warn!("reading directory entry failed - {}", err);
+                    None
+                }
+            })
+            .filter(|path| path.extension().is_some_and(|ext| ext == "desktop"))
+            .flat_map(|path| match Entry::read(&path) {
+                Ok(entry) => Some(entry),
+                Err(err) => {
+                    warn!("ignoring {:?} due to error - {}", path, err);
+                    None
+                }
+            })
+            .collect();
+
+        Ok(entries)
+    }
+}
+
+pub struct Entry {
+    path: PathBuf,
+    file_name: OsString,
+    name: String,
+    enabled: Cell<bool>,
+}
+
+/// Entries are identified by their file name, as that's what's overridden between directories.
+impl EntryId for &Entry {
+    fn id(&self) -> egui::Id {
+        egui::Id::new(&self.file_name)
+    }
+}
+
+impl Entry {
+    fn read(path: &Path) -> Result<Self> {
+        let contents = fs::read_to_string(path)
+            .wrap_err_with(|| format!("failed to read desktop file {path:?}"))?;
+        let file = DesktopFile::parse(&contents)
+            .map_err(|err| eyre!("{err}"))
+            .wrap_err_with(|| format!("failed to parse desktop file {path:?}"))?;
+        let desktop_entry = DesktopEntry::try_from_file(&file)
+            .wrap_err_with(|| format!("failed to parse desktop entry {path:?}"))?;
+
+        // entries are enabled unless they're hidden, or explicitly disabled by GNOME's extension key
+        let hidden = desktop_entry.common.hidden;
+        let gnome_enabled = desktop_entry
+            .group
+            .get_or("X-GNOME-Autostart-enabled", true)
+            .wrap_err_with(|| format!("failed to parse X-GNOME-Autostart-enabled in {path:?}"))?;
+
+        Ok(Self {
+            path: path.to_owned(),
+            file_name: path.file_name().expect("path should be a file").to_owned(),
+            name: desktop_entry.common.name,
+            enabled: Cell::new(!hidden && gnome_enabled),
+        })
+    }
+
+    /// Writes a copy of this entry to `user_directory` which is enabled or disabled, returning the
+    /// path of the written file.
+    fn write_override(&self, user_directory: &Path, enabled: bool) -> Result<PathBuf> {
+        let path = &self.path;
+        let contents = fs::read_to_string(path)
+            .wrap_err_with(|| format!("failed to read desktop file {path:?}"))?;
+        let mut file = DesktopFile::parse(&contents)
+            .map_err(|err| eyre!("{err}"))
+            .wrap_err_with(|| format!("failed to parse desktop file {path:?}"))?;
+
+        let group = file
+            .group_mut("Desktop Entry")
+            .ok_or_else(|| eyre!("desktop file {path:?} has no [Desktop Entry] group"))?;
+        group.set_raw("Hidden", (!enabled).to_string());
+        if group.get_raw("X-GNOME-Autostart-enabled").is_some() {
+            group.set_raw("X-GNOME-Autostart-enabled", enabled.to_string());
+        }
+
+        fs::create_dir_all(user_directory)
+            .wrap_err_with(|| format!("failed to create directory {user_directory:?}"))?;
+        let override_path = user_directory.join(&self.file_name);
+        fs::write(&override_path, file.to_string())
+            .wrap_err_with(|| format!("failed to write desktop file {override_path:?}"))?;
+
+        Ok(override_path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write(path: &Path, contents: &str) {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, contents).unwrap();
+    }
+
+    #[test]
+    fn read_enabled() {
+        let directory = tempfile::tempdir().unwrap();
+        let path = directory.path().join("entry.desktop");
+        let enabled = |extra: &str| {
+            write(
+                &path,
+                &format!("[Desktop Entry]\nType=Application\nName=Entry\nExec=entry\n{extra}"),
+            );
+            Entry::read(&path).unwrap().enabled.get()
+        };
+
+        assert!(enabled(""));
+        assert!(enabled("Hidden=false\nX-GNOME-Autostart-enabled=true\n"));
+        // either key disables the entry
+        assert!(!enabled("Hidden=true\n"));
+        assert!(!enabled("X-GNOME-Autostart-enabled=false\n"));
+        assert!(!enabled("Hidden=true\nX-GNOME-Autostart-enabled=true\n"));
+    }
+
+    #[test]
+    fn write_override() {
+        let directory = tempfile::tempdir().unwrap();
+        let system = directory.path().join("xdg/autostart");
+        let user = directory.path().join("config/autostart");
+        let plain = system.join("plain.desktop");
+        let gnome = system.join("gnome.desktop");
+        write(
+            &plain,
+            "[Desktop Entry]\nType=Application\nName=Plain\nExec=plain\n",
+        );
+        write(
+            &gnome,
+            concat!(
+                "[Desktop Entry]\nType=Application\nName=GNOME\nExec=gnome\n",
+                "X-GNOME-Autostart-enabled=true\n",
+            ),
+        );
+
+        // the GNOME key is only written if it was already there...
+        let written = Entry::read(&plain)
+            .unwrap()
+            .write_override(&user, false)
+            .unwrap();
+        assert_eq!(written, user.join("plain.desktop"));
+        let contents = fs::read_to_string(&written).unwrap();
+        let file = DesktopFile::parse(&contents).unwrap();
+        let group = file.group("Desktop Entry").unwrap();
+        assert_eq!(group.get_raw("Hidden"), Some("true"));
+        assert_eq!(group.get_raw("X-GNOME-Autostart-enabled"), None);
+        assert!(!Entry::read(&written).unwrap().enabled.get());
+
+        // ... in which case it's updated too
+        let written = Entry::read(&gnome)
+            .unwrap()
+            .write_override(&user, false)
+            .unwrap();
+        let contents = fs::read_to_string(&written).unwrap();
+        let file = DesktopFile::parse(&contents).unwrap();
+        let group = file.group("Desktop Entry").unwrap();
+        assert_eq!(group.get_raw("Hidden"), Some("true"));
+        assert_eq!(group.get_raw("X-GNOME-Autostart-enabled"), Some("false"));
+
+        // and enabling the override enables it again
+        let written = Entry::read(&written)
+            .unwrap()
+            .write_override(&user, true)
+            .unwrap();
+        let entry = Entry::read(&written).unwrap();
+        assert_eq!(entry.name, "GNOME");
+        assert!(entry.enabled.get());
+    }
+
+    #[test]
+    fn user_overrides_system() {
+        let directory = tempfile::tempdir().unwrap();
+        let system = directory.path().join("xdg/autostart");
+        let user = directory.path().join("config/autostart");
+        write(
+            &system.join("app.desktop"),
+            "[Desktop Entry]\nType=Application\nName=App\nExec=app\n",
+        );
+        write(
+            &system.join("other.desktop"),
+            "[Desktop Entry]\nType=Application\nName=Other\nExec=other\n",
+        );
+        write(
+            &user.join("app.desktop"),
+            "[Desktop Entry]\nType=Application\nName=App\nExec=app\nHidden=true\n",
+        );
+
+        let entries = Autostart::read_directories([system.clone(), user.clone()]);
+        let entries = entries
+            .iter()
+            .map(|entry| (entry.name.as_str(), entry.path.clone(), entry.enabled.get()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            entries,
+            [
+                ("App", user.join("app.desktop"), false),
+                ("Other", system.join("other.desktop"), true),
+            ]
+        );
+    }
+}
@@ -1,23 +1,57 @@
-use std::env::{self, VarError};
+use std::borrow::Cow;
+use std::cell::RefCell;
+use std::cmp::Reverse;
+use std::collections::HashMap;
+use std::env;
 use std::fs;
 use std::path::{Path, PathBuf};
 use std::process::Command;
+use std::sync::Arc;
+use std::time::SystemTime;
 
-use color_eyre::eyre::{ensure, eyre, Context, Report, Result};
-use desktop_file::desktop_entry::{DesktopEntry, DesktopEntryType, Exec, ExecArgument};
-use desktop_file::DesktopFile;
+use color_eyre::eyre::{bail, eyre, Context, Report, Result};
+use desktop_file::desktop_entry::{DesktopEntry, DesktopEntryType, Exec};
+use desktop_file::{desktop_file_id, DesktopFile, Locale, OwnedDesktopFile};
 use eframe::egui;
-use itertools::chain;
+use fuzzy_matcher::skim::SkimMatcherV2;
+use fuzzy_matcher::FuzzyMatcher;
+use itertools::{chain, Itertools};
 use log::{error, info, trace, warn};
-use serde::Deserialize;
+use regex::{Regex, RegexBuilder};
+use serde::{Deserialize, Serialize};
 
-use crate::backends::{Backend, Entries, EntriesCounter, NewBackend};
+use crate::backends::incremental::{Collector, Incremental};
+use crate::backends::usage::Usage;
+use crate::backends::{
+    default_max_results, highlighted_text, is_executable, xdg, Backend, Entries, NewBackend,
+};
+use crate::toffee::EntryId;
+
+use self::cache::{Cache, Source};
+use self::icon::Icons;
+
+mod cache;
+mod icon;
+
+/// Size icons are shown at, in points.
+const ICON_SIZE: f32 = 24.0;
 
 pub struct DRun {
-    entries: Vec<Entry>,
+    /// Entries are read on a background thread, so the list grows as they're read.
+    entries: Incremental<Entry>,
+    /// Starts reading the entries again, for [`Backend::reload`].
+    read: Box<dyn Fn() -> Incremental<Entry>>,
+    elevate: Option<Vec<String>>,
+    terminal: Vec<String>,
+    opener: Vec<String>,
+    search: Search,
+    weights: Weights,
+    max_results: usize,
+    matcher: SkimMatcherV2,
+    usage: RefCell<Usage>,
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, Clone)]
 #[serde(untagged)]
 enum Paths {
     None,
@@ -41,6 +75,43 @@ impl Default for Paths {
     }
 }
 
+/// How the query is matched against entries.
+#[derive(Deserialize, Default, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+enum Search {
+    /// Entries containing the query's characters in order, best matches first.
+    #[default]
+    Fuzzy,
+    /// Entries containing the query, ignoring case.
+    Substring,
+    /// Entries matching the query as a regular expression.
+    Regex,
+}
+
+/// How much a match in each of an entry's fields counts towards its score. The score of a match in
+/// a field is multiplied by the field's weight, and the best weighted score is the entry's score.
+#[derive(Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(default)]
+struct Weights {
+    name: i64,
+    generic_name: i64,
+    keywords: i64,
+    /// The name of the program an application runs, without its directory (e.g. `gimp`).
+    program: i64,
+}
+
+/// Names rank above generic names, which rank above keywords, which rank above program names.
+impl Default for Weights {
+    fn default() -> Self {
+        Self {
+            name: 4,
+            generic_name: 3,
+            keywords: 2,
+            program: 1,
+        }
+    }
+}
+
 // why is serde like this
 fn bool_true() -> bool {
     true
@@ -54,117 +125,360 @@ pub struct Config {
     include_user: bool,
     #[serde(default)]
     include: Paths,
+    /// Command to prefix entries with when launched with the alternate action, e.g. `["pkexec"]`.
+    /// Elevated launches are disabled if unset.
+    elevate: Option<Vec<String>>,
+    /// Command to run entries with `Terminal=true` in, e.g. `["xterm", "-e"]`. Defaults to
+    /// `$TERMINAL -e` if `$TERMINAL` is set, or `xterm -e` otherwise.
+    terminal: Option<Vec<String>>,
+    /// Command to open link entries' URLs with, e.g. `["firefox"]`. Defaults to `xdg-open`.
+    opener: Option<Vec<String>>,
+    /// Locale to show names and comments in, and to search localized keywords in (in addition to
+    /// the default keywords), e.g. `de_AT`.
+    locale: Option<String>,
+    /// How to match entries against the query - `"fuzzy"` (the default), `"substring"` or
+    /// `"regex"`.
+    #[serde(default)]
+    search: Search,
+    /// How much matches in each field count, e.g.
+    /// `{ name = 4, generic_name = 3, keywords = 2, program = 1 }` (the default).
+    #[serde(default)]
+    weights: Weights,
+    /// Maximum number of entries to show, after the best matches are sorted first. Defaults to 200.
+    #[serde(default = "default_max_results")]
+    max_results: usize,
+    /// Icon theme to look up entries' icons in before the `hicolor` theme, e.g. `Papirus`.
+    icon_theme: Option<String>,
 }
 
-fn xdg(name: &str, default: &[impl AsRef<Path>]) -> Result<Vec<PathBuf>> {
-    let value = match env::var(name) {
-        Ok(value) => Ok(Some(value)),
-        Err(VarError::NotPresent) => Ok(None),
-        Err(err) => Err(err).wrap_err_with(|| format!("failed to read ${name}")),
-    }?;
+impl NewBackend for DRun {
+    type Config = Config;
 
-    let paths: Vec<PathBuf> = match value {
-        Some(value) => value.split(':').map(|path| path.into()).collect(),
-        None => default.iter().map(|path| path.as_ref().into()).collect(),
-    };
+    fn new(cc: &eframe::CreationContext<'_>, config: Self::Config) -> Result<Self> {
+        let include = include_directories(&config)?;
 
-    let paths = paths
-        .iter()
-        .map(|path| {
-            ensure!(
-                path.is_absolute(),
-                "path {path:?} in ${name} should be absolute"
-            );
+        let locale = config.locale.and_then(|locale| {
+            let parsed = Locale::parse(&locale);
+            if parsed.is_none() {
+                warn!("ignoring invalid locale {:?}", locale);
+            }
 
-            Ok(path.join("applications"))
-        })
-        .collect::<Result<_, _>>()?;
+            parsed
+        });
 
-    Ok(paths)
-}
+        let context = ReadContext {
+            locale,
+            current_desktops: current_desktops(),
+            path: env::var_os("PATH")
+                .map(|path| env::split_paths(&path).collect())
+                .unwrap_or_default(),
+            icon_theme: config.icon_theme,
+        };
 
-impl NewBackend for DRun {
-    type Config = Config;
+        let cache_directory = env::var("HOME")
+            .wrap_err("$HOME should be set")
+            .and_then(|home| {
+                let default = PathBuf::from(home).join(".cache");
+                xdg("XDG_CACHE_HOME", &[default], "toffee")
+            })
+            .wrap_err("failed to find the cache directory")?
+            .swap_remove(0);
 
-    fn new(_cc: &eframe::CreationContext<'_>, config: Self::Config) -> Self {
-        let include_system = config
-            .include_system
-            .then(|| xdg("XDG_DATA_DIRS", &["/usr/local/share", "/usr/share"]))
-            .transpose()
-            .expect("include_system paths to be ok") // TODO: report error properly
-            .unwrap_or_default();
-        let include_user = config
-            .include_user
-            .then(|| {
-                let home = env::var("HOME").wrap_err("$HOME should be set")?;
-                let default = PathBuf::from(home).join(".local/share");
-
-                xdg("XDG_DATA_HOME", &[default])
+        // as per the Icon Theme Specification, $HOME/.icons takes precedence over $XDG_DATA_DIRS
+        let icon_dirs = env::var("HOME")
+            .wrap_err("$HOME should be set")
+            .and_then(|home| {
+                let default = PathBuf::from(&home).join(".local/share");
+                let data_home = xdg("XDG_DATA_HOME", &[default], "icons")?;
+                let data_dirs = xdg(
+                    "XDG_DATA_DIRS",
+                    &["/usr/local/share", "/usr/share"],
+                    "icons",
+                )?;
+                Ok(
+                    chain!([PathBuf::from(home).join(".icons")], data_home, data_dirs)
+                        .collect_vec(),
+                )
             })
-            .transpose()
-            .expect("include-user paths to be ok") // TODO: report error properly
-            .unwrap_or_default();
+            .wrap_err("failed to find the icon directories")?;
+        let icons = Icons::new(
+            &icon_dirs,
+            vec![PathBuf::from("/usr/share/pixmaps")],
+            context.icon_theme.as_deref(),
+        );
 
-        let include = chain!(include_system, include_user, config.include.into_vec());
-        let entries = include
-            .flat_map(|path| {
-                Self::read_entries(path).unwrap_or_else(|err| {
-                    warn!("failed to read entries - {}", err);
+        let cache_path = cache_directory.join("drun.cache");
+        let ctx = cc.egui_ctx.clone();
+        let read = move || {
+            let (include, context, icons, cache_path) = (
+                include.clone(),
+                context.clone(),
+                icons.clone(),
+                cache_path.clone(),
+            );
+            let ctx = ctx.clone();
+            Incremental::spawn(
+                move |collector| {
+                    Self::read_entries(include, &context, &icons, &cache_path, collector)
+                },
+                move || ctx.request_repaint(),
+            )
+        };
+        let entries = read();
 
-                    vec![]
-                })
-            })
-            .collect();
+        let usage_path = cache_directory.join("drun-usage.json");
+        let usage = Usage::load(usage_path.clone()).unwrap_or_else(|err| {
+            warn!("ignoring usage - {}", err);
+            Usage::new(usage_path)
+        });
 
-        Self { entries }
+        Ok(Self {
+            entries,
+            read: Box::new(read),
+            elevate: config.elevate,
+            terminal: config.terminal.unwrap_or_else(default_terminal),
+            opener: config
+                .opener
+                .unwrap_or_else(|| vec!["xdg-open".to_string()]),
+            search: config.search,
+            weights: config.weights,
+            max_results: config.max_results,
+            matcher: SkimMatcherV2::default(),
+            usage: RefCell::new(usage),
+        })
     }
 }
 
-impl<'entry> Backend<'entry> for DRun {
-    type Entry = &'entry Entry;
+/// The directories to read desktop files from, in increasing order of precedence.
+fn include_directories(config: &Config) -> Result<Vec<PathBuf>> {
+    let include_system = config
+        .include_system
+        .then(|| {
+            xdg(
+                "XDG_DATA_DIRS",
+                &["/usr/local/share", "/usr/share"],
+                "applications",
+            )
+        })
+        .transpose()
+        .wrap_err("failed to find the system applications directories")?
+        .unwrap_or_default();
+    let include_user = config
+        .include_user
+        .then(|| {
+            let home = env::var("HOME").wrap_err("$HOME should be set")?;
+            let default = PathBuf::from(home).join(".local/share");
 
-    fn entries(&'entry self, query: &str) -> Entries<Self::Entry> {
-        let entries = self
-            .entries
-            .iter()
-            .filter(|entry| {
-                let query = &query.to_lowercase();
+            xdg("XDG_DATA_HOME", &[default], "applications")
+        })
+        .transpose()
+        .wrap_err("failed to find the user applications directory")?
+        .unwrap_or_default();
 
-                let name_match = entry.name.to_lowercase().contains(query);
-                let keyword_match = entry
-                    .keywords
-                    .iter()
-                    .any(|k| k.to_lowercase().contains(query));
+    let include = config.include.clone().into_vec();
+    if let Some(path) = include.iter().find(|path| !path.is_absolute()) {
+        bail!("include path {path:?} should be absolute");
+    }
 
-                name_match || keyword_match
-            })
-            .collect::<Vec<_>>();
+    // $XDG_DATA_DIRS is in decreasing order of precedence, so the directories are reversed to put
+    // everything in increasing order of precedence
+    Ok(chain!(include_system.into_iter().rev(), include_user, include).collect_vec())
+}
 
-        Entries {
-            counter: Some(EntriesCounter {
-                visible: entries.len(),
-                total: self.entries.len(),
-            }),
+impl<'entry> Backend<'entry> for DRun {
+    type Entry = Match;
+
+    fn entries(&'entry self, query: &str) -> Result<Entries<Self::Entry>> {
+        let all_entries = self.entries.snapshot();
+        let usage = self.usage.borrow();
+        let mut entries = match self.search {
+            Search::Fuzzy => matching_entries(
+                &self.matcher,
+                &all_entries,
+                query,
+                &self.weights,
+                usage.counts(),
+            ),
+            search => {
+                // nothing matches until the query is valid
+                let regexes = query_regexes(search, query)
+                    .wrap_err_with(|| format!("invalid regex {query:?}"))?;
+                pattern_entries(&regexes, &all_entries, &self.weights, usage.counts())
+            }
+        };
+        // stable, so entries stay ranked by score within each kind of match
+        entries.sort_by_key(|m| NameMatch::new(&m.entry.name, query));
+
+        Ok(Entries::with_limit(
             entries,
-        }
+            self.max_results,
+            all_entries.len(),
+        ))
     }
 
-    fn entry_contents(&self, ui: &mut egui::Ui, entry: Self::Entry) {
-        ui.label(&entry.name);
+    fn entry_contents(
+        &self,
+        ui: &mut egui::Ui,
+        Match {
+            entry,
+            name_indices,
+        }: Self::Entry,
+    ) {
+        ui.horizontal(|ui| {
+            let icon_size = egui::Vec2::splat(ICON_SIZE);
+            match &entry.icon_path {
+                // loaded textures are cached by egui's loaders, by the file's URI
+                Some(path) => {
+                    let uri = format!("file://{}", path.display());
+                    ui.add(egui::Image::new(uri).fit_to_exact_size(icon_size));
+                }
+                // leave a gap so names stay aligned
+                None => {
+                    ui.allocate_space(icon_size);
+                }
+            }
+
+            let response = ui.label(highlighted_text(ui, &entry.name, &name_indices));
+            if let Some(tooltip) = entry.tooltip() {
+                response.on_hover_text(tooltip);
+            }
+        });
     }
 
-    fn on_selected(&self, entry: Self::Entry) {
-        match entry.launch() {
-            Ok(_) => {}
+    fn entry_completion(&self, Match { entry, .. }: Self::Entry) -> Option<String> {
+        Some(entry.name.clone())
+    }
+
+    fn on_selected(&self, Match { entry, .. }: Self::Entry) {
+        match entry.launch(&[], &self.terminal, &self.opener) {
+            Ok(_) => self.record_usage(&entry),
             Err(err) => {
                 error!("launch failed - {}", err);
             }
         }
     }
+
+    fn on_selected_alternate(&self, selected: Self::Entry) {
+        let Some(elevate) = &self.elevate else {
+            warn!("not launching elevated, no elevate command configured");
+            return self.on_selected(selected);
+        };
+
+        match selected.entry.launch(elevate, &self.terminal, &self.opener) {
+            Ok(_) => self.record_usage(&selected.entry),
+            Err(err) => {
+                error!("elevated launch failed - {}", err);
+            }
+        }
+    }
+
+    fn reload(&mut self) {
+        // reading the entries twice at once would have both scans write the cache
+        if self.entries.is_loading() {
+            info!("not reloading, the entries are still being read");
+            return;
+        }
+
+        info!("reloading entries");
+        self.entries = (self.read)();
+    }
 }
 
 impl DRun {
-    fn read_entries<P: AsRef<Path>>(path: P) -> Result<Vec<Entry>, String> {
+    fn record_usage(&self, entry: &Entry) {
+        let mut usage = self.usage.borrow_mut();
+        usage.increment(&entry.path);
+        if let Err(err) = usage.save() {
+            warn!("failed to save usage - {}", err);
+        }
+    }
+
+    /// Reads the entries from the desktop files in the directories `include` (in increasing order
+    /// of precedence), adding each to `collector` once its icon has been found in `icons`.
+    ///
+    /// Entries are reused from the cache at `cache_path` if none of their files have changed, and
+    /// the cache is then replaced with the entries which were read.
+    fn read_entries(
+        include: Vec<PathBuf>,
+        context: &ReadContext,
+        icons: &Icons,
+        cache_path: &Path,
+        collector: &Collector<Entry>,
+    ) {
+        let files = include.into_iter().flat_map(|path| {
+            Self::list_files(path).unwrap_or_else(|err| {
+                warn!("failed to read entries - {}", err);
+
+                vec![]
+            })
+        });
+
+        // entries for files which have since been removed are left behind in the old cache
+        let mut old_cache = Cache::load(cache_path, context);
+        let mut cache = Cache::new(context);
+        for (id, sources) in group_sources(files) {
+            let (mut entry, cacheable) = match old_cache.take(&id, &sources) {
+                Some(entry) => (entry, true),
+                None => match Self::read_entry(&id, &sources, context) {
+                    Some(read) => read,
+                    None => continue,
+                },
+            };
+
+            if let Some(entry) = &mut entry {
+                entry.find_icon(icons);
+                collector.push(entry.clone());
+            }
+            if cacheable {
+                cache.insert(id, sources, entry);
+            }
+        }
+
+        if let Err(err) = cache.save(cache_path) {
+            warn!("failed to save cache - {}", err);
+        }
+    }
+
+    /// Reads and merges the desktop files in `sources`, which all have the desktop file ID `id`.
+    /// Returns the entry (`None` if it isn't shown) and whether it can be cached, or `None` if it
+    /// couldn't be read.
+    ///
+    /// Entries with a `TryExec` key aren't cached, as whether they're shown depends on what's
+    /// installed rather than just the files they're read from.
+    fn read_entry(
+        id: &str,
+        sources: &[Source],
+        context: &ReadContext,
+    ) -> Option<(Option<Entry>, bool)> {
+        let files = sources.iter().flat_map(|(path, _)| match read_file(path) {
+            Ok(file) => Some((id.to_string(), path.clone(), file)),
+            Err(err) => {
+                warn!("ignoring {:?} due to error - {}", path, err);
+                None
+            }
+        });
+        let (path, file) = merge_files(files).pop()?;
+        let cacheable = !file
+            .group("Desktop Entry")
+            .is_some_and(|group| group.contains_key("TryExec"));
+
+        // Parse the entry, reporting entries ignored due to errors
+        match Entry::from_file(&path, &file, context) {
+            EntryResult::Ok(entry) => Some((Some(entry), cacheable)),
+            EntryResult::Ignored => {
+                trace!("ignoring {:?}", path);
+                Some((None, cacheable))
+            }
+            EntryResult::Err(err) => {
+                warn!("ignoring {:?} due to error - {}", path, err);
+                None
+            }
+        }
+    }
+
+    /// Lists the desktop files in the directory `path`, returning the desktop file ID, path and
+    /// modification time of each.
+    fn list_files<P: AsRef<Path>>(path: P) -> Result<Vec<DesktopFileSource>, String> {
         let path = path.as_ref();
 
         trace!("reading entries from {:?}", path);
@@ -172,7 +486,7 @@ impl DRun {
         let dir = fs::read_dir(path)
             .map_err(|err| format!("couldn't read directory {:?} - {}", path, err))?;
 
-        let entries = dir
+        let files = dir
             // Unwrap directory entries, reporting errors
             .flat_map(|dir_entry| match dir_entry {
                 Ok(dir_entry) => {
@@ -184,24 +498,340 @@ impl DRun {
                     None
                 }
             })
-            // Read each file, reporting entries ignored due to errors
-            .flat_map(|dir_entry| match Entry::read(dir_entry.path()) {
-                EntryResult::Ok(entry) => Some(entry),
-                EntryResult::Ignored => {
-                    trace!("ignoring {:?}", dir_entry);
-                    None
-                }
-                EntryResult::Err(err) => {
-                    warn!("ignoring {:?} due to error - {}", dir_entry, err);
-                    None
+            // Find when each desktop file was modified, reporting files ignored due to errors
+            .flat_map(|dir_entry| {
+                let file_path = dir_entry.path();
+                let Some(id) = desktop_file_id(path, &file_path) else {
+                    trace!("ignoring {:?}, not a desktop file", dir_entry);
+                    return None;
+                };
+
+                match fs::metadata(&file_path).and_then(|metadata| metadata.modified()) {
+                    Ok(modified) => Some((id, file_path, modified)),
+                    Err(err) => {
+                        warn!("ignoring {:?} due to error - {}", dir_entry, err);
+                        None
+                    }
                 }
             })
             .collect();
 
-        Ok(entries)
+        Ok(files)
+    }
+}
+
+/// A desktop file's ID, path, and contents.
+type DesktopFileEntry = (String, PathBuf, OwnedDesktopFile);
+
+/// A desktop file's ID, path, and modification time.
+type DesktopFileSource = (String, PathBuf, SystemTime);
+
+/// Groups desktop files by their ID, where `files` are in increasing order of precedence. The
+/// files for each ID stay in that order, and IDs are in the order they were first seen.
+fn group_sources(files: impl IntoIterator<Item = DesktopFileSource>) -> Vec<(String, Vec<Source>)> {
+    let mut grouped: Vec<(String, Vec<Source>)> = vec![];
+    let mut indices: HashMap<String, usize> = HashMap::new();
+    for (id, path, modified) in files {
+        match indices.get(&id) {
+            Some(&index) => grouped[index].1.push((path, modified)),
+            None => {
+                indices.insert(id.clone(), grouped.len());
+                grouped.push((id, vec![(path, modified)]));
+            }
+        }
+    }
+
+    grouped
+}
+
+/// Reads the desktop file at `path`, keeping only the `[Desktop Entry]` group.
+fn read_file(path: &Path) -> Result<OwnedDesktopFile> {
+    let contents = fs::read_to_string(path)
+        .wrap_err_with(|| format!("failed to read desktop file {path:?}"))?;
+    // only the [Desktop Entry] group is read, so don't bother keeping the others
+    let file = DesktopFile::parse_group(&contents, "Desktop Entry")
+        .map_err(|_| eyre!("TODO: fix errors from desktop-file"))
+        .wrap_err_with(|| format!("failed to parse desktop file {path:?}"))?;
+
+    Ok(file.into_owned())
+}
+
+/// Merges desktop files with the same ID, where `files` are in increasing order of precedence.
+/// Keys in a later file override those in an earlier file with the same ID, and the path of the
+/// latest file is kept. Files stay in the order their IDs were first seen.
+fn merge_files(
+    files: impl IntoIterator<Item = DesktopFileEntry>,
+) -> Vec<(PathBuf, OwnedDesktopFile)> {
+    let mut merged: Vec<(PathBuf, OwnedDesktopFile)> = vec![];
+    let mut indices: HashMap<String, usize> = HashMap::new();
+    for (id, path, file) in files {
+        match indices.get(&id) {
+            Some(&index) => {
+                trace!("{:?} overrides {:?}", path, merged[index].0);
+
+                let (merged_path, merged_file) = &mut merged[index];
+                *merged_path = path;
+                merged_file.merge(file);
+            }
+            None => {
+                indices.insert(id, merged.len());
+                merged.push((path, file));
+            }
+        }
+    }
+
+    merged
+}
+
+/// The terminal command used when none is configured - `$TERMINAL -e`, falling back to `xterm -e`.
+fn default_terminal() -> Vec<String> {
+    let terminal = env::var("TERMINAL")
+        .ok()
+        .filter(|terminal| !terminal.is_empty())
+        .unwrap_or_else(|| "xterm".to_string());
+
+    vec![terminal, "-e".to_string()]
+}
+
+/// An entry which matched the query.
+#[derive(Clone)]
+pub struct Match {
+    entry: Arc<Entry>,
+    /// Indices of the characters in the entry's name which matched the query.
+    name_indices: Vec<usize>,
+}
+
+impl EntryId for Match {
+    fn id(&self) -> egui::Id {
+        egui::Id::new(&self.entry.path)
+    }
+}
+
+/// Fuzzy matches each whitespace-separated term of `query` against each entry's fields,
+/// returning the entries which match every term ordered by [`rank`]. An empty query matches every
+/// entry equally.
+fn matching_entries(
+    matcher: &SkimMatcherV2,
+    entries: &[Arc<Entry>],
+    query: &str,
+    weights: &Weights,
+    usage: &HashMap<PathBuf, u64>,
+) -> Vec<Match> {
+    let terms = query.split_whitespace().collect_vec();
+    scored_entries(entries, &terms, weights, usage, |text, term| {
+        matcher.fuzzy_indices(text, term)
+    })
+}
+
+/// Builds the regexes to match entries against for the [`Search::Substring`] and [`Search::Regex`]
+/// search modes, where an entry must match all of them. Each whitespace-separated term of a
+/// substring query is matched separately, but a regex is matched as a whole. An empty query has
+/// no regexes.
+fn query_regexes(search: Search, query: &str) -> Result<Vec<Regex>, regex::Error> {
+    match search {
+        Search::Substring => query
+            .split_whitespace()
+            .map(|term| query_regex(search, term))
+            .collect(),
+        _ if query.is_empty() => Ok(vec![]),
+        _ => Ok(vec![query_regex(search, query)?]),
+    }
+}
+
+/// Builds the regex to match entries against for the [`Search::Substring`] and [`Search::Regex`]
+/// search modes.
+fn query_regex(search: Search, query: &str) -> Result<Regex, regex::Error> {
+    let substring = search == Search::Substring;
+    let pattern = if substring {
+        regex::escape(query)
+    } else {
+        query.to_string()
+    };
+
+    RegexBuilder::new(&pattern)
+        .case_insensitive(substring)
+        .build()
+}
+
+/// Matches `regexes` against each entry's fields, returning the entries which match all
+/// of them ordered by [`rank`]. Every match in the same field scores the same, so matches are
+/// otherwise only ordered by usage. No regexes match every entry.
+fn pattern_entries(
+    regexes: &[Regex],
+    entries: &[Arc<Entry>],
+    weights: &Weights,
+    usage: &HashMap<PathBuf, u64>,
+) -> Vec<Match> {
+    scored_entries(entries, regexes, weights, usage, |text, regex| {
+        let found = regex.find(text)?;
+        let indices = text
+            .char_indices()
+            .enumerate()
+            .filter(|(_, (byte_index, _))| found.range().contains(byte_index))
+            .map(|(char_index, _)| char_index)
+            .collect();
+
+        Some((1, indices))
+    })
+}
+
+/// Scores each entry's name, generic name, keywords and program against each of the `terms` with
+/// `score`, which returns the score and the indices of the matched characters of the text if it
+/// matches the term. Returns the entries which match every term ordered by [`rank`], using the sum
+/// of each term's best score in any field after it's weighted by `weights`. No terms match every
+/// entry equally.
+fn scored_entries<Term>(
+    entries: &[Arc<Entry>],
+    terms: &[Term],
+    weights: &Weights,
+    usage: &HashMap<PathBuf, u64>,
+    score: impl Fn(&str, &Term) -> Option<(i64, Vec<usize>)>,
+) -> Vec<Match> {
+    let matches = entries.iter().flat_map(|entry| {
+        let mut total_score = 0;
+        let mut name_indices = vec![];
+        for term in terms {
+            let score = |text: &str| score(text, term);
+            let name_match = score(&entry.name);
+            let name_score = name_match.as_ref().map(|(score, _)| score * weights.name);
+            let generic_name_score = entry
+                .generic_name
+                .as_deref()
+                .and_then(score)
+                .map(|(score, _)| score * weights.generic_name);
+            let keyword_score = entry
+                .keywords
+                .iter()
+                .flat_map(|keyword| score(keyword))
+                .map(|(score, _)| score * weights.keywords)
+                .max();
+            let program_score = entry
+                .program()
+                .and_then(score)
+                .map(|(score, _)| score * weights.program);
+            total_score +=
+                chain!(name_score, generic_name_score, keyword_score, program_score).max()?;
+
+            name_indices.extend(name_match.map(|(_, indices)| indices).unwrap_or_default());
+        }
+        // terms can match overlapping parts of the name
+        name_indices.sort_unstable();
+        name_indices.dedup();
+
+        Some((
+            total_score,
+            Match {
+                entry: entry.clone(),
+                name_indices,
+            },
+        ))
+    });
+
+    rank(matches, usage)
+}
+
+/// How an entry's name matches the whole query, ignoring case. Better kinds of match are shown
+/// first regardless of their score, as someone typing the start of a name expects that entry first.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+enum NameMatch {
+    /// The name starts with the query.
+    Prefix,
+    /// The name contains the query.
+    Contains,
+    /// The entry matched some other way, e.g. on a keyword.
+    Other,
+}
+
+impl NameMatch {
+    fn new(name: &str, query: &str) -> Self {
+        let name = name.to_lowercase();
+        let query = query.trim().to_lowercase();
+        if name.starts_with(&query) {
+            Self::Prefix
+        } else if name.contains(&query) {
+            Self::Contains
+        } else {
+            Self::Other
+        }
+    }
+}
+
+/// Orders scored matches from best to worst score. Matches with equal scores are ordered by how
+/// many times they've been launched according to `usage`, and then stay in their original order.
+fn rank(matches: impl Iterator<Item = (i64, Match)>, usage: &HashMap<PathBuf, u64>) -> Vec<Match> {
+    matches
+        // stable, so entries with equal scores and usage stay in their original order
+        .sorted_by_key(|(score, m)| {
+            let count = usage.get(&m.entry.path).copied().unwrap_or(0);
+            Reverse((*score, count))
+        })
+        .map(|(_, m)| m)
+        .collect()
+}
+
+/// Details of the environment used while reading entries.
+#[derive(Clone)]
+struct ReadContext {
+    /// Locale to show names and comments in, and to read additional keywords for.
+    locale: Option<Locale>,
+    /// Desktop environments to show entries for, from `$XDG_CURRENT_DESKTOP`.
+    current_desktops: Vec<String>,
+    /// Directories to find `TryExec` programs in, from `$PATH`.
+    path: Vec<PathBuf>,
+    /// Icon theme to find entries' icons in before the `hicolor` theme.
+    icon_theme: Option<String>,
+}
+
+/// Whether the `TryExec` program `program` is installed, i.e. it's the absolute path to an
+/// executable file, or the name of an executable file in one of the `path` directories.
+fn program_exists(program: &str, path: &[PathBuf]) -> bool {
+    let program = Path::new(program);
+    if program.is_absolute() {
+        is_executable(program)
+    } else {
+        path.iter()
+            .any(|directory| is_executable(&directory.join(program)))
     }
 }
 
+/// Reads the colon-separated list of current desktop environments from `$XDG_CURRENT_DESKTOP`,
+/// which is empty if it isn't set.
+fn current_desktops() -> Vec<String> {
+    env::var("XDG_CURRENT_DESKTOP")
+        .map(|desktops| {
+            desktops
+                .split(':')
+                .filter(|desktop| !desktop.is_empty())
+                .map(String::from)
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Whether an entry with the given `OnlyShowIn` and `NotShowIn` keys should be shown in any of the
+/// `current_desktops`. Desktop names are compared case-sensitively.
+///
+/// Entries restricted with `OnlyShowIn` aren't shown when there are no current desktops.
+fn shown_in(
+    only_show_in: Option<&[String]>,
+    not_show_in: Option<&[String]>,
+    current_desktops: &[String],
+) -> bool {
+    let intersects = |desktops: &[String]| {
+        desktops
+            .iter()
+            .any(|desktop| current_desktops.contains(desktop))
+    };
+
+    let only_show_in = match only_show_in {
+        Some(desktops) => intersects(desktops),
+        None => true,
+    };
+    let not_show_in = not_show_in.is_some_and(intersects);
+
+    only_show_in && !not_show_in
+}
+
 pub enum EntryResult<T, E> {
     Ok(T),
     Ignored,
@@ -218,67 +848,1198 @@ impl<T, E> From<Result<Option<T>, E>> for EntryResult<T, E> {
     }
 }
 
+#[derive(Serialize, Deserialize, Clone)]
 pub struct Entry {
+    /// Path to the desktop file the entry was read from.
+    path: PathBuf,
     name: String,
+    generic_name: Option<String>,
+    comment: Option<String>,
+    icon: Option<String>,
+    /// The file `icon` was found in, found while the entry is read as finding it means searching
+    /// the icon themes.
+    icon_path: Option<PathBuf>,
     keywords: Vec<String>,
-    exec: Exec,
+    kind: EntryKind,
+}
+
+/// What an entry does when it's launched.
+#[derive(Serialize, Deserialize, Clone)]
+enum EntryKind {
+    /// Runs a program.
+    Application {
+        #[serde(with = "cache::exec")]
+        exec: Exec,
+        /// Whether the program must be run in a terminal.
+        terminal: bool,
+        /// Directory to run the program in, if not the current directory.
+        working_directory: Option<PathBuf>,
+    },
+    /// Opens a URL.
+    Link { url: String },
+    /// Activates an application over D-Bus, by its application ID.
+    DBus { app_id: String },
 }
 
 impl Entry {
-    fn read<P: AsRef<Path>>(path: P) -> EntryResult<Self, Report> {
-        fn read(path: &Path) -> Result<Option<Entry>> {
+    /// The name of the program the entry runs, without its directory (e.g. `gimp` for
+    /// `/usr/bin/gimp`), if it runs a program.
+    fn program(&self) -> Option<&str> {
+        match &self.kind {
+            EntryKind::Application { exec, .. } => Path::new(&exec.program).file_name()?.to_str(),
+            _ => None,
+        }
+    }
+
+    /// Reads an entry from the desktop `file` at `path`.
+    fn from_file(
+        path: &Path,
+        file: &DesktopFile,
+        context: &ReadContext,
+    ) -> EntryResult<Self, Report> {
+        fn from_file(
+            path: &Path,
+            file: &DesktopFile,
+            context: &ReadContext,
+        ) -> Result<Option<Entry>> {
             // Hack to avoid having to move to nightly to implement Try for EntryResult
             #[allow(non_snake_case)]
             let Ignored = Ok(None);
 
-            let contents = fs::read_to_string(path)
-                .wrap_err_with(|| format!("failed to read desktop file {path:?}"))?;
-            let file = DesktopFile::parse(&contents)
-                .map_err(|_| eyre!("TODO: fix errors from desktop-file"))
-                .wrap_err_with(|| format!("failed to parse desktop file {path:?}"))?;
-            let desktop_entry = DesktopEntry::try_from_file(&file)
+            let desktop_entry = DesktopEntry::try_from_file(file)
                 .wrap_err_with(|| format!("failed to parse desktop entry {path:?}"))?;
+            let localized_keywords = context
+                .locale
+                .as_ref()
+                .map(|locale| desktop_entry.localized_keywords(locale))
+                .transpose()
+                .wrap_err_with(|| format!("failed to parse localized keywords {path:?}"))?
+                .flatten()
+                .unwrap_or_default();
+            let locale = context.locale.as_ref();
+            let name = desktop_entry.name(locale).into_owned();
+            let generic_name = desktop_entry.generic_name(locale).map(Cow::into_owned);
+            let comment = desktop_entry.comment(locale).map(Cow::into_owned);
 
             let common = desktop_entry.common;
-            let app = match desktop_entry.for_type {
-                DesktopEntryType::Application(app) => app,
-                _ => return Ignored,
-            };
 
-            let name = common.name;
-            let keywords = app.keywords.unwrap_or_default();
-            let exec = match app.exec {
-                Some(exec) => exec,
-                None => return Ignored,
+            // hidden entries are treated as deleted, and entries without display shouldn't be shown
+            // in menus
+            if common.hidden || common.no_display {
+                return Ignored;
+            }
+            if !shown_in(
+                common.only_show_in.as_deref(),
+                common.not_show_in.as_deref(),
+                &context.current_desktops,
+            ) {
+                return Ignored;
+            }
+
+            // entries for programs which aren't installed shouldn't be shown
+            if let DesktopEntryType::Application(app) = &desktop_entry.for_type {
+                if let Some(try_exec) = &app.try_exec {
+                    if !program_exists(try_exec, &context.path) {
+                        trace!("ignoring {:?}, TryExec {:?} not found", path, try_exec);
+                        return Ignored;
+                    }
+                }
+            }
+
+            let icon = common.icon;
+            let (keywords, kind) = match desktop_entry.for_type {
+                // D-Bus activation is preferred if possible, with Exec as a fallback
+                DesktopEntryType::Application(app)
+                    if cfg!(feature = "dbus") && app.dbus_activatable =>
+                {
+                    let app_id = match app_id(path) {
+                        Some(app_id) => app_id,
+                        None => return Ignored,
+                    };
+
+                    (app.keywords, EntryKind::DBus { app_id })
+                }
+                DesktopEntryType::Application(app) => {
+                    let exec = match app.exec {
+                        Some(exec) => exec,
+                        None => return Ignored,
+                    };
+                    let kind = EntryKind::Application {
+                        exec,
+                        terminal: app.terminal,
+                        working_directory: app.path,
+                    };
+
+                    (app.keywords, kind)
+                }
+                DesktopEntryType::Link(link) => (None, EntryKind::Link { url: link.url }),
+                _ => return Ignored,
             };
+            // search both the localized and default keywords
+            let keywords = chain!(localized_keywords, keywords.unwrap_or_default())
+                .unique()
+                .collect();
 
             Ok(Some(Entry {
+                path: path.to_owned(),
                 name,
+                generic_name,
+                comment,
+                icon,
+                icon_path: None,
                 keywords,
-                exec,
+                kind,
             }))
         }
 
-        read(path.as_ref()).into()
+        from_file(path, file, context).into()
     }
 
-    fn launch(&self) -> Result<()> {
-        let Exec { program, arguments } = &self.exec;
-        let arguments = arguments
-            .iter()
-            .flat_map(|argument| match argument {
-                ExecArgument::String(s) => Some(s.clone()),
-                ExecArgument::FieldCode(_) => None,
-            })
-            .collect::<Vec<String>>();
+    /// Finds the file for the entry's icon in `icons`, unless the file found when it was last read
+    /// (e.g. from the cache) still exists.
+    fn find_icon(&mut self, icons: &Icons) {
+        if self.icon_path.as_ref().is_some_and(|path| path.is_file()) {
+            return;
+        }
+
+        self.icon_path = self
+            .icon
+            .as_deref()
+            .and_then(|icon| icons.find(icon, ICON_SIZE as u32));
+    }
+
+    /// Text to show when hovering over the entry - the generic name and comment, on separate
+    /// lines, if either are present.
+    fn tooltip(&self) -> Option<String> {
+        let lines = chain!(&self.generic_name, &self.comment)
+            .map(String::as_str)
+            .collect::<Vec<_>>();
 
-        info!("launching {:?} with arguments {:?}", program, arguments);
+        (!lines.is_empty()).then(|| lines.join("\n"))
+    }
+
+    /// Builds the command to launch this entry, prefixed by `prefix` (e.g. a privilege escalation
+    /// command such as `pkexec`) if it is non-empty. Applications which must be run in a terminal
+    /// are run in the `terminal` command, and links are opened with the `opener` command.
+    ///
+    /// Applications are run in their working directory, if they have one and it exists.
+    ///
+    /// Returns [`None`] for entries which are activated over D-Bus rather than run.
+    fn command(
+        &self,
+        prefix: &[String],
+        terminal: &[String],
+        opener: &[String],
+    ) -> Option<Command> {
+        let arguments = match &self.kind {
+            EntryKind::Application {
+                exec,
+                terminal: in_terminal,
+                ..
+            } => {
+                let arguments = exec.expand(
+                    &[],
+                    None,
+                    &self.name,
+                    self.icon.as_deref(),
+                    Some(&self.path),
+                );
+                let terminal = if *in_terminal { terminal } else { &[] };
+                chain!(terminal, prefix, &arguments).cloned().collect_vec()
+            }
+            EntryKind::Link { url } => chain!(prefix, opener, [url]).cloned().collect_vec(),
+            EntryKind::DBus { .. } => return None,
+        };
+        let mut arguments = arguments.into_iter();
+
+        // the expanded command line always contains at least the program
+        let program = arguments
+            .next()
+            .expect("command line should contain a program");
+        let mut command = Command::new(program);
+        command.args(arguments);
+        if let EntryKind::Application {
+            working_directory: Some(working_directory),
+            ..
+        } = &self.kind
+        {
+            // better to start somewhere unexpected than not at all
+            if working_directory.is_dir() {
+                command.current_dir(working_directory);
+            } else {
+                warn!(
+                    "working directory {:?} doesn't exist, using the current directory",
+                    working_directory
+                );
+            }
+        }
+        Some(command)
+    }
+
+    fn launch(&self, prefix: &[String], terminal: &[String], opener: &[String]) -> Result<()> {
+        let Some(mut command) = self.command(prefix, terminal, opener) else {
+            let EntryKind::DBus { app_id } = &self.kind else {
+                unreachable!("only D-Bus activated entries have no command");
+            };
+            if !prefix.is_empty() {
+                warn!("ignoring prefix {:?} for D-Bus activation", prefix);
+            }
 
-        Command::new(program)
-            .args(arguments)
-            .spawn()
-            .wrap_err("spawn failed")?;
+            info!("activating {:?} over D-Bus", app_id);
+            return activate(app_id);
+        };
+
+        info!(
+            "launching {:?} with arguments {:?}",
+            command.get_program(),
+            command.get_args().collect::<Vec<_>>()
+        );
+
+        command.spawn().wrap_err("spawn failed")?;
 
         Ok(())
     }
 }
+
+/// The D-Bus application ID of the desktop file at `path` - its file name, without the `.desktop`
+/// extension.
+fn app_id(path: &Path) -> Option<String> {
+    let file_name = path.file_name()?.to_str()?;
+    let app_id = file_name.strip_suffix(".desktop")?;
+
+    Some(app_id.to_string())
+}
+
+/// The object path of the application with the given D-Bus application ID, e.g.
+/// `/org/example/Foo_Bar` for `org.example.Foo-Bar`.
+#[cfg_attr(not(feature = "dbus"), allow(dead_code))]
+fn dbus_object_path(app_id: &str) -> String {
+    format!("/{}", app_id.replace('.', "/").replace('-', "_"))
+}
+
+/// Activates the application with the given D-Bus application ID, with
+/// `org.freedesktop.Application.Activate`.
+#[cfg(feature = "dbus")]
+fn activate(app_id: &str) -> Result<()> {
+    use zbus::blocking::Connection;
+    use zbus::zvariant::Value;
+
+    let connection = Connection::session().wrap_err("failed to connect to the session bus")?;
+    // no startup notification or activation token to pass on
+    let platform_data: HashMap<&str, Value> = HashMap::new();
+    connection
+        .call_method(
+            Some(app_id),
+            dbus_object_path(app_id).as_str(),
+            Some("org.freedesktop.Application"),
+            "Activate",
+            &(platform_data,),
+        )
+        .wrap_err("activation failed")?;
+
+    Ok(())
+}
+
+#[cfg(not(feature = "dbus"))]
+fn activate(app_id: &str) -> Result<()> {
+    Err(eyre!(
+        "can't activate {app_id:?}, toffee was built without the dbus feature"
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::os::unix::fs::PermissionsExt;
+    use std::rc::Rc;
+    use std::sync::mpsc;
+
+    use desktop_file::FromRaw;
+
+    use super::*;
+
+    pub(super) fn entry(name: &str, keywords: &[&str]) -> Entry {
+        Entry {
+            path: PathBuf::from(format!("/{name}.desktop")),
+            name: name.to_string(),
+            generic_name: None,
+            comment: None,
+            icon: None,
+            icon_path: None,
+            keywords: keywords.iter().map(|k| k.to_string()).collect(),
+            kind: EntryKind::Application {
+                exec: Exec::from_raw("true").unwrap(),
+                terminal: false,
+                working_directory: None,
+            },
+        }
+    }
+
+    fn command_line(command: &Command) -> Vec<&std::ffi::OsStr> {
+        chain!([command.get_program()], command.get_args()).collect()
+    }
+
+    fn names(matches: &[Match]) -> Vec<&str> {
+        matches.iter().map(|m| m.entry.name.as_str()).collect()
+    }
+
+    fn drun(entries: Vec<Entry>, search: Search, max_results: usize) -> DRun {
+        let read = move || Incremental::from_vec(entries.clone());
+        DRun {
+            entries: read(),
+            read: Box::new(read),
+            elevate: None,
+            terminal: vec![],
+            opener: vec![],
+            search,
+            weights: Weights::default(),
+            max_results,
+            matcher: SkimMatcherV2::default(),
+            usage: RefCell::new(Usage::new(PathBuf::from("/nonexistent/drun-usage.json"))),
+        }
+    }
+
+    #[test]
+    fn reload() {
+        let source = Rc::new(RefCell::new(vec![entry("Firefox", &[])]));
+        let mut drun = drun(vec![], Search::Fuzzy, 10);
+        let read_source = source.clone();
+        drun.read = Box::new(move || Incremental::from_vec(read_source.borrow().clone()));
+        let entries = |drun: &DRun| {
+            let matches = drun.entries("").unwrap().entries;
+            names(&matches)
+                .iter()
+                .map(ToString::to_string)
+                .collect_vec()
+        };
+        assert!(entries(&drun).is_empty());
+
+        drun.reload();
+        assert_eq!(entries(&drun), ["Firefox"]);
+
+        // new entries are only shown once the entries are reloaded
+        source.borrow_mut().push(entry("Text Editor", &[]));
+        assert_eq!(entries(&drun), ["Firefox"]);
+        drun.reload();
+        assert_eq!(entries(&drun), ["Firefox", "Text Editor"]);
+
+        // entries aren't reloaded while they're still being read
+        let (done_tx, done_rx) = mpsc::channel::<()>();
+        drun.entries = Incremental::spawn(
+            move |_| {
+                let _ = done_rx.recv();
+            },
+            || {},
+        );
+        drun.reload();
+        assert!(entries(&drun).is_empty());
+        drop(done_tx);
+    }
+
+    #[test]
+    fn entries_max_results() {
+        let drun = drun(
+            vec![
+                entry("Firefox", &[]),
+                entry("Riff Studio", &[]),
+                entry("Text Editor", &[]),
+                entry("Fiffer", &[]),
+            ],
+            Search::Fuzzy,
+            2,
+        );
+
+        // only the best matches are kept, but all of them are counted
+        let entries = drun.entries("ff").unwrap();
+        assert_eq!(names(&entries.entries), ["Fiffer", "Riff Studio"]);
+        let counter = entries.counter.unwrap();
+        assert_eq!((counter.visible, counter.total), (3, 4));
+    }
+
+    #[test]
+    fn entries_prefix_first() {
+        let entries = vec![
+            entry("Console", &["terminal"]),
+            entry("GNOME Terminal Server", &[]),
+            entry("Terminal", &[]),
+        ];
+
+        // name matches all score the same when matching substrings, but prefixes come first...
+        let substring = drun(entries.clone(), Search::Substring, 10);
+        assert_eq!(
+            names(&substring.entries("term").unwrap().entries),
+            ["Terminal", "GNOME Terminal Server", "Console"]
+        );
+        // ... whatever the search
+        let fuzzy = drun(entries, Search::Fuzzy, 10);
+        assert_eq!(
+            names(&fuzzy.entries("term").unwrap().entries),
+            ["Terminal", "GNOME Terminal Server", "Console"]
+        );
+    }
+
+    #[test]
+    fn name_match_kinds() {
+        assert_eq!(NameMatch::new("Terminal", "term"), NameMatch::Prefix);
+        assert_eq!(
+            NameMatch::new("GNOME Terminal", "TERM"),
+            NameMatch::Contains
+        );
+        assert_eq!(NameMatch::new("Console", "term"), NameMatch::Other);
+        // an empty query is a prefix of everything, so the order is unchanged
+        assert_eq!(NameMatch::new("Console", ""), NameMatch::Prefix);
+    }
+
+    #[test]
+    fn matching_entries_fuzzy() {
+        let matcher = SkimMatcherV2::default();
+        let entries = [
+            entry("Text Editor", &["text", "editor"]),
+            entry("Riff Studio", &["music", "audio"]),
+            entry("Firefox", &["web", "browser"]),
+            entry("Terminal", &["shell"]),
+        ]
+        .map(Arc::new);
+
+        assert_eq!(
+            names(&matching_entries(
+                &matcher,
+                &entries,
+                "ff",
+                &Weights::default(),
+                &HashMap::new()
+            )),
+            ["Firefox", "Riff Studio"]
+        );
+        // keywords are matched too
+        assert_eq!(
+            names(&matching_entries(
+                &matcher,
+                &entries,
+                "browser",
+                &Weights::default(),
+                &HashMap::new()
+            )),
+            ["Firefox"]
+        );
+        assert!(matching_entries(
+            &matcher,
+            &entries,
+            "xyz",
+            &Weights::default(),
+            &HashMap::new()
+        )
+        .is_empty());
+    }
+
+    #[test]
+    fn matching_entries_weights() {
+        let matcher = SkimMatcherV2::default();
+        let entries = [
+            Entry {
+                generic_name: Some("Web Browser".to_string()),
+                ..entry("Firefox", &["internet"])
+            },
+            entry("Browsh", &[]),
+            entry("Links", &["browser"]),
+        ]
+        .map(Arc::new);
+
+        // a match in the name outranks one in the generic name, which outranks one in a keyword
+        assert_eq!(
+            names(&matching_entries(
+                &matcher,
+                &entries,
+                "brows",
+                &Weights::default(),
+                &HashMap::new()
+            )),
+            ["Browsh", "Firefox", "Links"]
+        );
+        // unless the weights say otherwise
+        let weights = Weights {
+            name: 1,
+            generic_name: 1,
+            keywords: 5,
+            program: 1,
+        };
+        assert_eq!(
+            names(&matching_entries(
+                &matcher,
+                &entries,
+                "brows",
+                &weights,
+                &HashMap::new()
+            ))[0],
+            "Links"
+        );
+    }
+
+    #[test]
+    fn matching_entries_program() {
+        let matcher = SkimMatcherV2::default();
+        let entries = [
+            Entry {
+                kind: EntryKind::Application {
+                    exec: Exec::from_raw("/usr/bin/gimp %U").unwrap(),
+                    terminal: false,
+                    working_directory: None,
+                },
+                ..entry("Image Editor", &[])
+            },
+            entry("Firefox", &[]),
+        ]
+        .map(Arc::new);
+
+        // the program's name matches, but not its directory
+        let substring = |query| {
+            let regexes = query_regexes(Search::Substring, query).unwrap();
+            let matches = pattern_entries(&regexes, &entries, &Weights::default(), &HashMap::new());
+            names(&matches)
+                .iter()
+                .map(ToString::to_string)
+                .collect_vec()
+        };
+        assert_eq!(substring("gimp"), ["Image Editor"]);
+        assert!(substring("bin").is_empty());
+
+        let matches = matching_entries(
+            &matcher,
+            &entries,
+            "gimp",
+            &Weights::default(),
+            &HashMap::new(),
+        );
+        assert_eq!(names(&matches), ["Image Editor"]);
+        // and nothing in the name is highlighted
+        assert!(matches[0].name_indices.is_empty());
+    }
+
+    #[test]
+    fn matching_entries_indices() {
+        let matcher = SkimMatcherV2::default();
+        let entries = [entry("Firefox", &["web", "browser"])].map(Arc::new);
+
+        let matches = matching_entries(
+            &matcher,
+            &entries,
+            "ff",
+            &Weights::default(),
+            &HashMap::new(),
+        );
+        assert_eq!(matches[0].name_indices, [0, 4]);
+        // only the name's characters are highlighted, so there's nothing to show for a keyword match
+        let matches = matching_entries(
+            &matcher,
+            &entries,
+            "browser",
+            &Weights::default(),
+            &HashMap::new(),
+        );
+        assert!(matches[0].name_indices.is_empty());
+    }
+
+    #[test]
+    fn matching_entries_empty_query() {
+        let matcher = SkimMatcherV2::default();
+        let entries = [entry("Text Editor", &[]), entry("Firefox", &[])].map(Arc::new);
+
+        // everything, in the original order
+        assert_eq!(
+            names(&matching_entries(
+                &matcher,
+                &entries,
+                "",
+                &Weights::default(),
+                &HashMap::new()
+            )),
+            ["Text Editor", "Firefox"]
+        );
+    }
+
+    #[test]
+    fn matching_entries_every_term() {
+        let matcher = SkimMatcherV2::default();
+        let entries = [
+            entry("Firefox", &["web", "browser"]),
+            entry("Firefox Developer Edition", &["web", "browser"]),
+        ]
+        .map(Arc::new);
+
+        let matches = matching_entries(
+            &matcher,
+            &entries,
+            "fire dev",
+            &Weights::default(),
+            &HashMap::new(),
+        );
+        assert_eq!(names(&matches), ["Firefox Developer Edition"]);
+        // both terms are highlighted
+        assert_eq!(matches[0].name_indices, [0, 1, 2, 3, 8, 9, 10]);
+        // terms can match different fields
+        assert_eq!(
+            names(&matching_entries(
+                &matcher,
+                &entries,
+                "dev browser",
+                &Weights::default(),
+                &HashMap::new()
+            )),
+            ["Firefox Developer Edition"]
+        );
+    }
+
+    #[test]
+    fn pattern_entries_every_term() {
+        let entries = [
+            entry("Firefox", &["web", "browser"]),
+            entry("Firefox Developer Edition", &["web", "browser"]),
+        ]
+        .map(Arc::new);
+
+        let regexes = query_regexes(Search::Substring, "fire dev").unwrap();
+        assert_eq!(
+            names(&pattern_entries(
+                &regexes,
+                &entries,
+                &Weights::default(),
+                &HashMap::new()
+            )),
+            ["Firefox Developer Edition"]
+        );
+    }
+
+    #[test]
+    fn query_regexes_terms() {
+        assert_eq!(
+            query_regexes(Search::Substring, " fire  dev ")
+                .unwrap()
+                .len(),
+            2
+        );
+        // a regex is matched as a whole, spaces and all
+        let regexes = query_regexes(Search::Regex, "fire dev").unwrap();
+        assert_eq!(regexes.len(), 1);
+        assert!(regexes[0].is_match("fire dev"));
+        // an empty query matches everything
+        assert!(query_regexes(Search::Substring, "").unwrap().is_empty());
+        assert!(query_regexes(Search::Regex, "").unwrap().is_empty());
+    }
+
+    #[test]
+    fn pattern_entries_regex() {
+        let entries = [
+            entry("Text Editor", &["text", "editor"]),
+            entry("Firefox", &["web", "browser"]),
+            entry("Terminal", &["shell"]),
+        ]
+        .map(Arc::new);
+
+        let regex = Regex::new("^T.*r").unwrap();
+        let matches = pattern_entries(&[regex], &entries, &Weights::default(), &HashMap::new());
+        assert_eq!(names(&matches), ["Text Editor", "Terminal"]);
+        // the whole match is highlighted
+        assert_eq!(matches[1].name_indices, [0, 1, 2]);
+        // keywords are matched too
+        let regex = Regex::new("brows|shell").unwrap();
+        assert_eq!(
+            names(&pattern_entries(
+                &[regex],
+                &entries,
+                &Weights::default(),
+                &HashMap::new()
+            )),
+            ["Firefox", "Terminal"]
+        );
+    }
+
+    #[test]
+    fn query_regex_modes() {
+        let regex = query_regex(Search::Regex, "^fire.ox$").unwrap();
+        assert!(regex.is_match("firefox"));
+        assert!(!regex.is_match("Firefox"));
+        // invalid regexes are an error rather than a panic
+        assert!(query_regex(Search::Regex, "fire(fox").is_err());
+
+        // substrings are escaped, and ignore case
+        let regex = query_regex(Search::Substring, "fire(fox").unwrap();
+        assert!(regex.is_match("Fire(fox)"));
+        assert!(!regex.is_match("firefox"));
+    }
+
+    #[test]
+    fn rank_usage() {
+        let entries = [
+            entry("Text Editor", &[]),
+            entry("Firefox", &[]),
+            entry("Terminal", &[]),
+            entry("Files", &[]),
+        ]
+        .map(Arc::new);
+        let usage = HashMap::from([
+            (entries[1].path.clone(), 10),
+            (entries[2].path.clone(), 3),
+            (entries[3].path.clone(), 3),
+        ]);
+        let scored = |scores: [i64; 4]| {
+            scores
+                .into_iter()
+                .zip(&entries)
+                .map(|(score, entry)| {
+                    let name_indices = vec![];
+                    (
+                        score,
+                        Match {
+                            entry: entry.clone(),
+                            name_indices,
+                        },
+                    )
+                })
+                .collect::<Vec<_>>()
+        };
+
+        // with equal scores (e.g. an empty query), the most used entries come first, and entries
+        // with equal usage stay in their original order
+        assert_eq!(
+            names(&rank(scored([0, 0, 0, 0]).into_iter(), &usage)),
+            ["Firefox", "Terminal", "Files", "Text Editor"]
+        );
+        // but a better match is always first
+        assert_eq!(
+            names(&rank(scored([50, 40, 40, 10]).into_iter(), &usage)),
+            ["Text Editor", "Firefox", "Terminal", "Files"]
+        );
+        // no usage keeps the original order
+        assert_eq!(
+            names(&rank(scored([0, 0, 0, 0]).into_iter(), &HashMap::new())),
+            ["Text Editor", "Firefox", "Terminal", "Files"]
+        );
+    }
+
+    fn parse(contents: &str) -> EntryResult<Entry, Report> {
+        parse_in(None, contents)
+    }
+
+    fn parse_in(locale: Option<&str>, contents: &str) -> EntryResult<Entry, Report> {
+        let context = ReadContext {
+            locale: locale.map(|locale| Locale::parse(locale).unwrap()),
+            current_desktops: vec![],
+            path: vec![],
+            icon_theme: None,
+        };
+        let file = DesktopFile::parse(contents).unwrap();
+
+        Entry::from_file(Path::new("/test.desktop"), &file, &context)
+    }
+
+    fn file_entry(id: &str, path: &str, contents: &str) -> DesktopFileEntry {
+        let file = DesktopFile::parse(contents).unwrap().into_owned();
+
+        (id.to_string(), PathBuf::from(path), file)
+    }
+
+    #[test]
+    fn group_sources_by_id() {
+        let modified = SystemTime::UNIX_EPOCH;
+        let file = |id: &str, path: &str| (id.to_string(), PathBuf::from(path), modified);
+        let grouped = group_sources([
+            file("firefox", "/usr/share/applications/firefox.desktop"),
+            file("htop", "/usr/share/applications/htop.desktop"),
+            file(
+                "firefox",
+                "/home/user/.local/share/applications/firefox.desktop",
+            ),
+        ]);
+
+        // IDs stay in the order they were first seen, and files in order of precedence
+        assert_eq!(
+            grouped,
+            [
+                (
+                    "firefox".to_string(),
+                    vec![
+                        (
+                            PathBuf::from("/usr/share/applications/firefox.desktop"),
+                            modified
+                        ),
+                        (
+                            PathBuf::from("/home/user/.local/share/applications/firefox.desktop"),
+                            modified
+                        ),
+                    ]
+                ),
+                (
+                    "htop".to_string(),
+                    vec![(
+                        PathBuf::from("/usr/share/applications/htop.desktop"),
+                        modified
+                    )]
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn merge_files_override() {
+        let files = [
+            file_entry(
+                "firefox",
+                "/usr/share/applications/firefox.desktop",
+                "[Desktop Entry]\nType=Application\nName=Firefox\nExec=firefox\nComment=Browser\n",
+            ),
+            file_entry(
+                "htop",
+                "/usr/share/applications/htop.desktop",
+                "[Desktop Entry]\nType=Application\nName=htop\nExec=htop\n",
+            ),
+            file_entry(
+                "firefox",
+                "/home/user/.local/share/applications/firefox.desktop",
+                "[Desktop Entry]\nName=My Firefox\nExec=firefox -P user\n",
+            ),
+        ];
+
+        let merged = merge_files(files);
+        assert_eq!(merged.len(), 2);
+
+        // the user's file takes precedence, but keys it doesn't override are kept
+        let (path, file) = &merged[0];
+        assert_eq!(
+            path,
+            Path::new("/home/user/.local/share/applications/firefox.desktop")
+        );
+        let group = file.group("Desktop Entry").unwrap();
+        assert_eq!(group.get_raw("Name"), Some("My Firefox"));
+        assert_eq!(group.get_raw("Exec"), Some("firefox -P user"));
+        assert_eq!(group.get_raw("Type"), Some("Application"));
+        assert_eq!(group.get_raw("Comment"), Some("Browser"));
+
+        let (path, _) = &merged[1];
+        assert_eq!(path, Path::new("/usr/share/applications/htop.desktop"));
+    }
+
+    fn desktops(desktops: &[&str]) -> Vec<String> {
+        desktops.iter().map(|desktop| desktop.to_string()).collect()
+    }
+
+    #[test]
+    fn shown_in_only_show_in() {
+        let only_show_in = desktops(&["KDE", "LXQt"]);
+        let only_show_in = Some(only_show_in.as_slice());
+
+        assert!(shown_in(only_show_in, None, &desktops(&["KDE"])));
+        assert!(shown_in(only_show_in, None, &desktops(&["GNOME", "LXQt"])));
+        assert!(!shown_in(only_show_in, None, &desktops(&["GNOME"])));
+        // comparisons are case-sensitive
+        assert!(!shown_in(only_show_in, None, &desktops(&["kde"])));
+    }
+
+    #[test]
+    fn shown_in_not_show_in() {
+        let not_show_in = desktops(&["GNOME"]);
+        let not_show_in = Some(not_show_in.as_slice());
+
+        assert!(!shown_in(None, not_show_in, &desktops(&["GNOME"])));
+        assert!(!shown_in(
+            None,
+            not_show_in,
+            &desktops(&["ubuntu", "GNOME"])
+        ));
+        assert!(shown_in(None, not_show_in, &desktops(&["KDE"])));
+        assert!(shown_in(None, not_show_in, &desktops(&["gnome"])));
+    }
+
+    #[test]
+    fn shown_in_no_current_desktops() {
+        let restricted = desktops(&["KDE"]);
+        let restricted = Some(restricted.as_slice());
+
+        // unrestricted entries are always shown...
+        assert!(shown_in(None, None, &[]));
+        assert!(shown_in(None, None, &desktops(&["KDE"])));
+        // ... and so are entries only hidden from other desktops...
+        assert!(shown_in(None, restricted, &[]));
+        // ... but not entries only shown in other desktops
+        assert!(!shown_in(restricted, None, &[]));
+    }
+
+    #[test]
+    fn include_directories_absolute() {
+        let config = toml::from_str::<Config>(concat!(
+            "include_system = false\n",
+            "include_user = false\n",
+            "include = [\"/opt/applications\", \"applications\"]\n",
+        ))
+        .unwrap();
+        let err = include_directories(&config).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            r#"include path "applications" should be absolute"#
+        );
+
+        let config = toml::from_str::<Config>(concat!(
+            "include_system = false\n",
+            "include_user = false\n",
+            "include = \"/opt/applications\"\n",
+        ))
+        .unwrap();
+        assert_eq!(
+            include_directories(&config).unwrap(),
+            [PathBuf::from("/opt/applications")]
+        );
+    }
+
+    #[test]
+    fn program_exists_in_path() {
+        let directory = tempfile::tempdir().unwrap();
+        let program = directory.path().join("firefox");
+        fs::write(&program, "#!/bin/sh\n").unwrap();
+        fs::set_permissions(&program, fs::Permissions::from_mode(0o755)).unwrap();
+        let path = [PathBuf::from("/nonexistent"), directory.path().to_owned()];
+
+        assert!(program_exists("firefox", &path));
+        assert!(!program_exists("firefox", &[]));
+        assert!(!program_exists("chromium", &path));
+        // absolute paths aren't looked up in $PATH
+        assert!(program_exists(program.to_str().unwrap(), &[]));
+        assert!(!program_exists("/nonexistent/firefox", &path));
+
+        // the program must be executable
+        fs::set_permissions(&program, fs::Permissions::from_mode(0o644)).unwrap();
+        assert!(!program_exists("firefox", &path));
+    }
+
+    #[test]
+    fn find_icon() {
+        let directory = tempfile::tempdir().unwrap();
+        let icons = Icons::new(&[], vec![directory.path().to_owned()], None);
+        let mut firefox = Entry {
+            icon: Some("firefox".to_string()),
+            ..entry("Firefox", &[])
+        };
+
+        firefox.find_icon(&icons);
+        assert_eq!(firefox.icon_path, None);
+
+        // an icon installed since the entry was read is found...
+        let png = directory.path().join("firefox.png");
+        fs::write(&png, "").unwrap();
+        firefox.find_icon(&icons);
+        assert_eq!(firefox.icon_path.as_ref(), Some(&png));
+
+        // ... and the file found before is kept while it exists
+        let svg = directory.path().join("firefox.svg");
+        fs::write(&svg, "").unwrap();
+        firefox.find_icon(&icons);
+        assert_eq!(firefox.icon_path.as_ref(), Some(&png));
+        fs::remove_file(&png).unwrap();
+        firefox.find_icon(&icons);
+        assert_eq!(firefox.icon_path, Some(svg));
+    }
+
+    #[test]
+    fn parse_try_exec_missing() {
+        let result = parse(concat!(
+            "[Desktop Entry]\nType=Application\nName=Missing\nExec=missing\n",
+            "TryExec=/nonexistent/missing\n",
+        ));
+        assert!(matches!(result, EntryResult::Ignored));
+    }
+
+    #[test]
+    fn parse_shown() {
+        let result = parse("[Desktop Entry]\nType=Application\nName=Firefox\nExec=firefox\n");
+        assert!(matches!(result, EntryResult::Ok(entry) if entry.name == "Firefox"));
+
+        let result = parse(
+            "[Desktop Entry]\nType=Application\nName=Firefox\nExec=firefox\nNoDisplay=false\n",
+        );
+        assert!(matches!(result, EntryResult::Ok(_)));
+    }
+
+    #[test]
+    fn parse_localized() {
+        let contents =
+            "[Desktop Entry]\nType=Application\nName=Files\nName[de]=Dateien\nExec=files\n";
+        let result = parse_in(Some("de_DE"), contents);
+        assert!(matches!(result, EntryResult::Ok(entry) if entry.name == "Dateien"));
+
+        let result = parse_in(Some("fr"), contents);
+        assert!(matches!(result, EntryResult::Ok(entry) if entry.name == "Files"));
+    }
+
+    #[test]
+    fn parse_no_display() {
+        let result = parse(
+            "[Desktop Entry]\nType=Application\nName=Handler\nExec=handler %u\nNoDisplay=true\n",
+        );
+        assert!(matches!(result, EntryResult::Ignored));
+    }
+
+    #[test]
+    fn parse_hidden() {
+        let result =
+            parse("[Desktop Entry]\nType=Application\nName=Deleted\nExec=deleted\nHidden=true\n");
+        assert!(matches!(result, EntryResult::Ignored));
+    }
+
+    #[test]
+    fn command_location_and_name() {
+        let program = Entry {
+            kind: EntryKind::Application {
+                exec: Exec::from_raw("program --file %k --name %c").unwrap(),
+                terminal: false,
+                working_directory: None,
+            },
+            ..entry("Program", &[])
+        };
+
+        // the desktop file's path and the entry's (localized) name are passed through
+        assert_eq!(
+            command_line(&program.command(&[], &[], &[]).unwrap()),
+            ["program", "--file", "/Program.desktop", "--name", "Program"]
+        );
+    }
+
+    #[test]
+    fn command_terminal() {
+        let xterm = ["xterm".to_string(), "-e".to_string()];
+        let htop = Entry {
+            kind: EntryKind::Application {
+                exec: Exec::from_raw("htop").unwrap(),
+                terminal: true,
+                working_directory: None,
+            },
+            ..entry("htop", &[])
+        };
+
+        assert_eq!(
+            command_line(&htop.command(&[], &xterm, &[]).unwrap()),
+            ["xterm", "-e", "htop"]
+        );
+        // the program is elevated, not the terminal
+        assert_eq!(
+            command_line(&htop.command(&["pkexec".to_string()], &xterm, &[]).unwrap()),
+            ["xterm", "-e", "pkexec", "htop"]
+        );
+
+        // entries which don't need a terminal aren't run in one
+        let firefox = entry("Firefox", &[]);
+        assert_eq!(
+            command_line(&firefox.command(&[], &xterm, &[]).unwrap()),
+            ["true"]
+        );
+    }
+
+    #[test]
+    fn command_working_directory() {
+        let dir = tempfile::tempdir().unwrap();
+        let with_working_directory = |working_directory: &Path| Entry {
+            kind: EntryKind::Application {
+                exec: Exec::from_raw("./game").unwrap(),
+                terminal: false,
+                working_directory: Some(working_directory.to_owned()),
+            },
+            ..entry("Game", &[])
+        };
+
+        let game = with_working_directory(dir.path());
+        let command = game.command(&[], &[], &[]).unwrap();
+        assert_eq!(command.get_current_dir(), Some(dir.path()));
+
+        // a missing directory is ignored
+        let game = with_working_directory(&dir.path().join("missing"));
+        let command = game.command(&[], &[], &[]).unwrap();
+        assert_eq!(command.get_current_dir(), None);
+
+        // as is no directory at all
+        let firefox = entry("Firefox", &[]);
+        let command = firefox.command(&[], &[], &[]).unwrap();
+        assert_eq!(command.get_current_dir(), None);
+    }
+
+    #[test]
+    fn parse_working_directory() {
+        let result = parse(
+            "[Desktop Entry]\nType=Application\nName=Game\nExec=./game\nPath=/opt/My\\sGame\n",
+        );
+        let EntryResult::Ok(game) = result else {
+            panic!("entry should be parsed");
+        };
+        let EntryKind::Application {
+            working_directory, ..
+        } = game.kind
+        else {
+            panic!("entry should be an application");
+        };
+        assert_eq!(
+            working_directory.as_deref(),
+            Some(Path::new("/opt/My Game"))
+        );
+    }
+
+    #[test]
+    fn command_link() {
+        let opener = ["xdg-open".to_string()];
+        let docs = Entry {
+            kind: EntryKind::Link {
+                url: "https://example.com/docs".to_string(),
+            },
+            ..entry("Docs", &[])
+        };
+
+        assert_eq!(
+            command_line(&docs.command(&[], &[], &opener).unwrap()),
+            ["xdg-open", "https://example.com/docs"]
+        );
+        // links aren't run in a terminal, and a configured opener is used
+        let firefox = ["firefox".to_string(), "--new-tab".to_string()];
+        assert_eq!(
+            command_line(&docs.command(&[], &["xterm".to_string()], &firefox).unwrap()),
+            ["firefox", "--new-tab", "https://example.com/docs"]
+        );
+    }
+
+    #[test]
+    fn parse_link() {
+        let result = parse("[Desktop Entry]\nType=Link\nName=Docs\nURL=https://example.com/\n");
+        let EntryResult::Ok(entry) = result else {
+            panic!("link should be parsed");
+        };
+        assert!(matches!(entry.kind, EntryKind::Link { url } if url == "https://example.com/"));
+    }
+
+    #[test]
+    fn parse_dbus_activatable() {
+        let result = parse(
+            "[Desktop Entry]\nType=Application\nName=Files\nExec=files\nDBusActivatable=true\n",
+        );
+        let EntryResult::Ok(entry) = result else {
+            panic!("entry should be parsed");
+        };
+        if cfg!(feature = "dbus") {
+            assert!(matches!(entry.kind, EntryKind::DBus { app_id } if app_id == "test"));
+        } else {
+            assert!(matches!(entry.kind, EntryKind::Application { .. }));
+        }
+
+        // without the dbus feature, there's no way to launch an entry without Exec
+        let result = parse("[Desktop Entry]\nType=Application\nName=Files\nDBusActivatable=true\n");
+        assert_eq!(matches!(result, EntryResult::Ok(_)), cfg!(feature = "dbus"));
+    }
+
+    #[test]
+    fn dbus_names() {
+        assert_eq!(
+            app_id(Path::new(
+                "/usr/share/applications/org.example.Foo-Bar.desktop"
+            ))
+            .as_deref(),
+            Some("org.example.Foo-Bar")
+        );
+        assert_eq!(app_id(Path::new("/usr/share/applications/foo.txt")), None);
+        assert_eq!(
+            dbus_object_path("org.example.Foo-Bar"),
+            "/org/example/Foo_Bar"
+        );
+    }
+}
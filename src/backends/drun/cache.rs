@@ -0,0 +1,275 @@
+use std::collections::HashMap;
+use std::fs;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use color_eyre::eyre::{Context, Result};
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+
+use super::{Entry, ReadContext};
+
+/// A desktop file an entry was read from, and when it was last modified.
+pub type Source = (PathBuf, SystemTime);
+
+/// Entries read on a previous run, so desktop files only need to be parsed again if they've
+/// changed. Stored as JSON.
+#[derive(Serialize, Deserialize)]
+pub struct Cache {
+    /// The locale the entries were read in, as entries must be read again if it changes.
+    locale: Option<String>,
+    /// The desktop environments the entries were read for, as for `locale`.
+    current_desktops: Vec<String>,
+    /// The icon theme the entries' icons were found in, as for `locale`.
+    icon_theme: Option<String>,
+    /// Entries by their desktop file ID.
+    entries: HashMap<String, CachedEntry>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct CachedEntry {
+    /// Every file merged into the entry, in increasing order of precedence.
+    sources: Vec<Source>,
+    /// The entry, or `None` if it isn't shown (e.g. it's hidden).
+    entry: Option<Entry>,
+}
+
+impl Cache {
+    /// An empty cache for entries read in `context`.
+    pub fn new(context: &ReadContext) -> Self {
+        Self {
+            locale: context.locale.as_ref().map(ToString::to_string),
+            current_desktops: context.current_desktops.clone(),
+            icon_theme: context.icon_theme.clone(),
+            entries: HashMap::new(),
+        }
+    }
+
+    /// Loads the cache stored in `path`. The cache is empty if the file doesn't exist, can't be
+    /// read, or was written for a different `context`.
+    pub fn load(path: &Path, context: &ReadContext) -> Self {
+        let cache = match fs::read_to_string(path) {
+            Ok(contents) => serde_json::from_str::<Self>(&contents)
+                .wrap_err_with(|| format!("failed to parse cache file {path:?}")),
+            Err(err) if err.kind() == ErrorKind::NotFound => return Self::new(context),
+            Err(err) => Err(err).wrap_err_with(|| format!("failed to read cache file {path:?}")),
+        };
+
+        let empty = Self::new(context);
+        match cache {
+            Ok(cache)
+                if cache.locale == empty.locale
+                    && cache.current_desktops == empty.current_desktops
+                    && cache.icon_theme == empty.icon_theme =>
+            {
+                cache
+            }
+            Ok(_) => {
+                info!("ignoring cache, the locale, current desktops or icon theme changed");
+                empty
+            }
+            Err(err) => {
+                warn!("ignoring cache - {:#}", err);
+                empty
+            }
+        }
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)
+                .wrap_err_with(|| format!("failed to create directory {parent:?}"))?;
+        }
+
+        let contents = serde_json::to_string(self).wrap_err("failed to serialize cache")?;
+        fs::write(path, contents)
+            .wrap_err_with(|| format!("failed to write cache file {path:?}"))?;
+
+        Ok(())
+    }
+
+    /// Removes the entry with the desktop file ID `id` from the cache, if it was read from exactly
+    /// the files in `sources` and none of them have been modified since. Returns `Some(None)` for
+    /// an entry which isn't shown.
+    pub fn take(&mut self, id: &str, sources: &[Source]) -> Option<Option<Entry>> {
+        let cached = self.entries.remove(id)?;
+        is_fresh(&cached.sources, sources).then_some(cached.entry)
+    }
+
+    pub fn insert(&mut self, id: String, sources: Vec<Source>, entry: Option<Entry>) {
+        self.entries.insert(id, CachedEntry { sources, entry });
+    }
+}
+
+/// Whether an entry read from the `cached` sources can be reused for the `current` sources, i.e.
+/// no files have been added, removed, or modified since.
+fn is_fresh(cached: &[Source], current: &[Source]) -> bool {
+    cached == current
+}
+
+/// Serializes [`Exec`](desktop_file::desktop_entry::Exec), which is defined in another crate.
+pub mod exec {
+    use desktop_file::desktop_entry::{Exec, ExecArgument};
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    #[derive(Serialize, Deserialize)]
+    enum Argument {
+        String(String),
+        FieldCode(char),
+    }
+
+    pub fn serialize<S: Serializer>(exec: &Exec, serializer: S) -> Result<S::Ok, S::Error> {
+        let arguments = exec
+            .arguments
+            .iter()
+            .map(|argument| match argument {
+                ExecArgument::String(s) => Argument::String(s.clone()),
+                ExecArgument::FieldCode(fc) => Argument::FieldCode(*fc),
+            })
+            .collect::<Vec<_>>();
+
+        (&exec.program, arguments).serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Exec, D::Error> {
+        let (program, arguments) = <(String, Vec<Argument>)>::deserialize(deserializer)?;
+        let arguments = arguments
+            .into_iter()
+            .map(|argument| match argument {
+                Argument::String(s) => ExecArgument::String(s),
+                Argument::FieldCode(fc) => ExecArgument::FieldCode(fc),
+            })
+            .collect();
+
+        Ok(Exec { program, arguments })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+    use crate::backends::drun::tests::entry;
+
+    fn source(path: &str, seconds: u64) -> Source {
+        (
+            PathBuf::from(path),
+            SystemTime::UNIX_EPOCH + Duration::from_secs(seconds),
+        )
+    }
+
+    fn context() -> ReadContext {
+        ReadContext {
+            locale: None,
+            current_desktops: vec!["GNOME".to_string()],
+            path: vec![],
+            icon_theme: None,
+        }
+    }
+
+    #[test]
+    fn fresh_when_unchanged() {
+        let sources = [source("/usr/share/applications/firefox.desktop", 100)];
+        assert!(is_fresh(&sources, &sources));
+
+        let overridden = [
+            source("/usr/share/applications/firefox.desktop", 100),
+            source("/home/user/.local/share/applications/firefox.desktop", 200),
+        ];
+        assert!(is_fresh(&overridden, &overridden));
+    }
+
+    #[test]
+    fn stale_when_changed() {
+        let sources = [source("/usr/share/applications/firefox.desktop", 100)];
+
+        // modified...
+        assert!(!is_fresh(
+            &sources,
+            &[source("/usr/share/applications/firefox.desktop", 101)]
+        ));
+        // ... moved...
+        assert!(!is_fresh(
+            &sources,
+            &[source("/usr/local/share/applications/firefox.desktop", 100)]
+        ));
+        // ... overridden by a new file...
+        assert!(!is_fresh(
+            &sources,
+            &[
+                source("/usr/share/applications/firefox.desktop", 100),
+                source("/home/user/.local/share/applications/firefox.desktop", 50),
+            ]
+        ));
+        // ... or removed
+        assert!(!is_fresh(&sources, &[]));
+    }
+
+    #[test]
+    fn take() {
+        let sources = vec![source("/firefox.desktop", 100)];
+        let mut cache = Cache::new(&context());
+        cache.insert(
+            "firefox.desktop".to_string(),
+            sources.clone(),
+            Some(entry("Firefox", &[])),
+        );
+        cache.insert("hidden.desktop".to_string(), sources.clone(), None);
+
+        let firefox = cache.take("firefox.desktop", &sources).unwrap().unwrap();
+        assert_eq!(firefox.name, "Firefox");
+        // entries which aren't shown are cached too
+        assert!(cache.take("hidden.desktop", &sources).unwrap().is_none());
+        // and entries are only taken once
+        assert!(cache.take("firefox.desktop", &sources).is_none());
+
+        // a stale entry is discarded
+        cache.insert(
+            "firefox.desktop".to_string(),
+            sources,
+            Some(entry("Firefox", &[])),
+        );
+        assert!(cache
+            .take("firefox.desktop", &[source("/firefox.desktop", 200)])
+            .is_none());
+        assert!(cache.entries.is_empty());
+    }
+
+    #[test]
+    fn round_trip() {
+        let directory = tempfile::tempdir().unwrap();
+        let path = directory.path().join("toffee/drun.cache");
+        let sources = vec![source("/firefox.desktop", 100)];
+
+        // a missing file is an empty cache
+        let mut cache = Cache::load(&path, &context());
+        assert!(cache.entries.is_empty());
+
+        cache.insert(
+            "firefox.desktop".to_string(),
+            sources.clone(),
+            Some(entry("Firefox", &["web"])),
+        );
+        cache.save(&path).unwrap();
+
+        let mut loaded = Cache::load(&path, &context());
+        let firefox = loaded.take("firefox.desktop", &sources).unwrap().unwrap();
+        assert_eq!(firefox.name, "Firefox");
+        assert_eq!(firefox.keywords, ["web"]);
+
+        // entries read for other desktops are read again
+        let kde = ReadContext {
+            current_desktops: vec!["KDE".to_string()],
+            ..context()
+        };
+        assert!(Cache::load(&path, &kde).entries.is_empty());
+        // ... as are entries whose icons were found in another theme
+        let papirus = ReadContext {
+            icon_theme: Some("Papirus".to_string()),
+            ..context()
+        };
+        assert!(Cache::load(&path, &papirus).entries.is_empty());
+    }
+}
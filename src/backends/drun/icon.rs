@@ -0,0 +1,179 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use itertools::chain;
+
+/// Image formats icons can be shown in, in order of preference.
+const EXTENSIONS: [&str; 2] = ["svg", "png"];
+
+/// Finds icon files by name, as a minimal subset of the Icon Theme Specification: themes don't
+/// inherit from each other (i.e. `index.theme` isn't read), and the size of each directory is
+/// taken from its name (e.g. `48x48` or `scalable`).
+#[derive(Clone)]
+pub struct Icons {
+    /// Theme directories to search, in decreasing order of precedence.
+    themes: Vec<PathBuf>,
+    /// Directories of icons which aren't part of a theme (e.g. `/usr/share/pixmaps`), searched
+    /// after every theme.
+    unthemed: Vec<PathBuf>,
+}
+
+impl Icons {
+    /// Searches the theme named `theme` (if any), and then `hicolor` (the fallback theme), in each
+    /// of the `base_dirs` (e.g. `/usr/share/icons`), which are in decreasing order of precedence.
+    pub fn new(base_dirs: &[PathBuf], unthemed: Vec<PathBuf>, theme: Option<&str>) -> Self {
+        let themes = chain!(theme, ["hicolor"])
+            .flat_map(|theme| base_dirs.iter().map(move |base_dir| base_dir.join(theme)))
+            .collect();
+
+        Self { themes, unthemed }
+    }
+
+    /// Finds the file for an entry's `Icon`, which is either the absolute path to the file or the
+    /// name of an icon to look up, preferring the icon closest to `size` pixels.
+    pub fn find(&self, icon: &str, size: u32) -> Option<PathBuf> {
+        let path = Path::new(icon);
+        if path.is_absolute() {
+            return path.is_file().then(|| path.to_path_buf());
+        }
+
+        // some entries name their icon with an extension, which isn't part of the name
+        let name = match path.extension().and_then(|extension| extension.to_str()) {
+            Some("png" | "svg" | "xpm") => path.file_stem()?.to_str()?,
+            _ => icon,
+        };
+
+        self.themes
+            .iter()
+            .find_map(|theme| find_in_theme(theme, name, size))
+            .or_else(|| self.unthemed.iter().find_map(|dir| find_in_dir(dir, name)))
+    }
+}
+
+/// Finds the icon `name` in any of the size directories of `theme`, and any context directory
+/// within them (e.g. `48x48/apps`), preferring the size closest to `size`.
+fn find_in_theme(theme: &Path, name: &str, size: u32) -> Option<PathBuf> {
+    fs::read_dir(theme)
+        .ok()?
+        .flatten()
+        .flat_map(|size_dir| {
+            let distance = size_distance(size_dir.file_name().to_str()?, size)?;
+            let contexts = fs::read_dir(size_dir.path()).ok()?;
+            Some(
+                contexts
+                    .flatten()
+                    .map(move |context| (distance, context.path())),
+            )
+        })
+        .flatten()
+        .flat_map(|(distance, context)| Some((distance, find_in_dir(&context, name)?)))
+        // the path breaks ties, as directories are listed in an arbitrary order
+        .min()
+        .map(|(_, path)| path)
+}
+
+fn find_in_dir(dir: &Path, name: &str) -> Option<PathBuf> {
+    EXTENSIONS
+        .iter()
+        .map(|extension| dir.join(format!("{name}.{extension}")))
+        .find(|path| path.is_file())
+}
+
+/// How far the size of the icons in the size directory `dir_name` (e.g. `48x48`, `24x24@2` or
+/// `scalable`) is from `size` pixels, or [`None`] if it isn't a size directory. Scalable icons are
+/// always the right size.
+fn size_distance(dir_name: &str, size: u32) -> Option<u32> {
+    if dir_name == "scalable" {
+        return Some(0);
+    }
+
+    let (dimensions, scale) = match dir_name.split_once('@') {
+        Some((dimensions, scale)) => (dimensions, scale.parse::<u32>().ok()?),
+        None => (dir_name, 1),
+    };
+    let (width, _) = dimensions.split_once('x')?;
+    let width = width.parse::<u32>().ok()?;
+
+    Some((width * scale).abs_diff(size))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::slice;
+
+    use super::*;
+
+    fn touch(path: &Path) {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, "").unwrap();
+    }
+
+    #[test]
+    fn size_distances() {
+        assert_eq!(size_distance("48x48", 32), Some(16));
+        assert_eq!(size_distance("16x16@2", 32), Some(0));
+        assert_eq!(size_distance("scalable", 32), Some(0));
+        assert_eq!(size_distance("symbolic", 32), None);
+        assert_eq!(size_distance("48x48@x", 32), None);
+    }
+
+    #[test]
+    fn find_named() {
+        let directory = tempfile::tempdir().unwrap();
+        let base = directory.path().join("icons");
+        let pixmaps = directory.path().join("pixmaps");
+        touch(&base.join("hicolor/16x16/apps/firefox.png"));
+        touch(&base.join("hicolor/32x32/apps/firefox.png"));
+        touch(&base.join("hicolor/48x48/apps/terminal.png"));
+        touch(&base.join("hicolor/scalable/apps/terminal.svg"));
+        touch(&base.join("Papirus/32x32/apps/terminal.svg"));
+        touch(&base.join("hicolor/32x32/places/folder.png"));
+        touch(&pixmaps.join("htop.png"));
+
+        let icons = Icons::new(
+            slice::from_ref(&base),
+            vec![pixmaps.clone()],
+            Some("Papirus"),
+        );
+
+        // the closest size is preferred...
+        assert_eq!(
+            icons.find("firefox", 28),
+            Some(base.join("hicolor/32x32/apps/firefox.png"))
+        );
+        // ... and icons in the configured theme are preferred over hicolor
+        assert_eq!(
+            icons.find("terminal", 48),
+            Some(base.join("Papirus/32x32/apps/terminal.svg"))
+        );
+        // icons are found in any context, or outside of a theme
+        assert_eq!(
+            icons.find("folder", 32),
+            Some(base.join("hicolor/32x32/places/folder.png"))
+        );
+        assert_eq!(icons.find("htop", 32), Some(pixmaps.join("htop.png")));
+        // extensions are ignored
+        assert_eq!(icons.find("htop.png", 32), Some(pixmaps.join("htop.png")));
+        assert_eq!(icons.find("missing", 32), None);
+
+        // without the theme, scalable icons are the best match for sizes without their own icons
+        let icons = Icons::new(slice::from_ref(&base), vec![], None);
+        assert_eq!(
+            icons.find("terminal", 64),
+            Some(base.join("hicolor/scalable/apps/terminal.svg"))
+        );
+    }
+
+    #[test]
+    fn find_absolute() {
+        let directory = tempfile::tempdir().unwrap();
+        let path = directory.path().join("icon.png");
+        touch(&path);
+        let icons = Icons::new(&[], vec![], None);
+
+        assert_eq!(icons.find(path.to_str().unwrap(), 32), Some(path.clone()));
+        // missing files aren't looked up as names
+        let missing = directory.path().join("missing.png");
+        assert_eq!(icons.find(missing.to_str().unwrap(), 32), None);
+    }
+}
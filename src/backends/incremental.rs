@@ -0,0 +1,154 @@
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::thread::{self, JoinHandle};
+
+/// Items which are collected on a background thread, and can be read while they're still being
+/// collected (e.g. so the window can be shown before every entry has been read).
+pub struct Incremental<T> {
+    items: Arc<Mutex<Vec<Arc<T>>>>,
+    /// The thread collecting the items, if they weren't all available up front.
+    thread: Option<JoinHandle<()>>,
+}
+
+impl<T: Send + Sync + 'static> Incremental<T> {
+    /// Runs `collect` on a background thread, which adds items with [`Collector::push`].
+    /// `on_push` is called after each item is added, e.g. to request a repaint.
+    pub fn spawn(
+        collect: impl FnOnce(&Collector<T>) + Send + 'static,
+        on_push: impl Fn() + Send + 'static,
+    ) -> Self {
+        let (mut incremental, collector) = Self::new(on_push);
+        incremental.thread = Some(thread::spawn(move || collect(&collector)));
+
+        incremental
+    }
+
+    /// Creates an empty set of items, and the collector which adds to it.
+    fn new(on_push: impl Fn() + Send + 'static) -> (Self, Collector<T>) {
+        let items = Arc::new(Mutex::new(vec![]));
+        let collector = Collector {
+            items: items.clone(),
+            on_push: Box::new(on_push),
+        };
+
+        (
+            Self {
+                items,
+                thread: None,
+            },
+            collector,
+        )
+    }
+
+    /// Items which have already been collected.
+    #[cfg(test)]
+    pub fn from_vec(items: Vec<T>) -> Self {
+        let items = items.into_iter().map(Arc::new).collect();
+        Self {
+            items: Arc::new(Mutex::new(items)),
+            thread: None,
+        }
+    }
+
+    /// Whether items are still being collected.
+    pub fn is_loading(&self) -> bool {
+        self.thread
+            .as_ref()
+            .is_some_and(|thread| !thread.is_finished())
+    }
+
+    /// The items collected so far, in the order they were added.
+    pub fn snapshot(&self) -> Vec<Arc<T>> {
+        lock(&self.items).clone()
+    }
+}
+
+/// Adds items to an [`Incremental`] from the background thread.
+pub struct Collector<T> {
+    items: Arc<Mutex<Vec<Arc<T>>>>,
+    on_push: Box<dyn Fn() + Send>,
+}
+
+impl<T> Collector<T> {
+    pub fn push(&self, item: T) {
+        lock(&self.items).push(Arc::new(item));
+        (self.on_push)();
+    }
+}
+
+/// Locks `items`, ignoring poisoning - a panic while collecting leaves the items collected before
+/// it, which are still fine to read.
+fn lock<T>(items: &Mutex<Vec<Arc<T>>>) -> MutexGuard<'_, Vec<Arc<T>>> {
+    items.lock().unwrap_or_else(|err| err.into_inner())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::mpsc;
+
+    use super::*;
+
+    fn values(items: &[Arc<u32>]) -> Vec<u32> {
+        items.iter().map(|item| **item).collect()
+    }
+
+    #[test]
+    fn items_visible_while_collecting() {
+        // the collector waits for each item to be sent, and reports when it's been pushed
+        let (item_tx, item_rx) = mpsc::channel::<u32>();
+        let (pushed_tx, pushed_rx) = mpsc::channel();
+        let incremental = Incremental::spawn(
+            move |collector| {
+                for item in item_rx {
+                    collector.push(item);
+                }
+            },
+            move || pushed_tx.send(()).unwrap(),
+        );
+        assert_eq!(values(&incremental.snapshot()), [] as [u32; 0]);
+
+        item_tx.send(1).unwrap();
+        pushed_rx.recv().unwrap();
+        assert_eq!(values(&incremental.snapshot()), [1]);
+
+        item_tx.send(2).unwrap();
+        item_tx.send(3).unwrap();
+        pushed_rx.recv().unwrap();
+        pushed_rx.recv().unwrap();
+        assert_eq!(values(&incremental.snapshot()), [1, 2, 3]);
+    }
+
+    #[test]
+    fn loading_until_collected() {
+        let (item_tx, item_rx) = mpsc::channel::<u32>();
+        let incremental = Incremental::spawn(
+            move |collector| {
+                for item in item_rx {
+                    collector.push(item);
+                }
+            },
+            || {},
+        );
+        item_tx.send(1).unwrap();
+        assert!(incremental.is_loading());
+
+        // collecting finishes once there are no more items to send
+        drop(item_tx);
+        while incremental.is_loading() {
+            thread::yield_now();
+        }
+        assert_eq!(values(&incremental.snapshot()), [1]);
+
+        assert!(!Incremental::from_vec(vec![1]).is_loading());
+    }
+
+    #[test]
+    fn snapshot_unaffected_by_later_items() {
+        let (incremental, collector) = Incremental::new(|| {});
+        collector.push(1);
+        let snapshot = incremental.snapshot();
+        collector.push(2);
+
+        assert_eq!(values(&snapshot), [1]);
+        assert_eq!(values(&incremental.snapshot()), [1, 2]);
+    }
+}
@@ -1,23 +1,103 @@
+mod autostart;
 mod drun;
+mod incremental;
+mod run;
+mod script;
+#[cfg(test)]
+pub mod test;
+mod usage;
 
+use std::env::{self, VarError};
+use std::fs;
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
+
+use color_eyre::eyre::{ensure, Context, Result};
 use eframe::egui;
 use serde::Deserialize;
 
+use crate::toffee::EntryId;
+
+pub use autostart::Autostart;
 pub use drun::DRun;
+pub use run::Run;
+pub use script::Script;
+
+/// Reads the list of base directories from the XDG environment variable `name` (or `default`, if it
+/// isn't set), and returns the `subdirectory` of each.
+fn xdg(name: &str, default: &[impl AsRef<Path>], subdirectory: &str) -> Result<Vec<PathBuf>> {
+    let value = match env::var(name) {
+        Ok(value) => Ok(Some(value)),
+        Err(VarError::NotPresent) => Ok(None),
+        Err(err) => Err(err).wrap_err_with(|| format!("failed to read ${name}")),
+    }?;
+
+    let paths: Vec<PathBuf> = match value {
+        Some(value) => value.split(':').map(|path| path.into()).collect(),
+        None => default.iter().map(|path| path.as_ref().into()).collect(),
+    };
+
+    let paths = paths
+        .iter()
+        .map(|path| {
+            ensure!(
+                path.is_absolute(),
+                "path {path:?} in ${name} should be absolute"
+            );
 
-pub trait NewBackend {
+            Ok(path.join(subdirectory))
+        })
+        .collect::<Result<_, _>>()?;
+
+    Ok(paths)
+}
+
+/// Whether `path` is a file (or a link to a file) which anyone can execute.
+fn is_executable(path: &Path) -> bool {
+    fs::metadata(path)
+        .is_ok_and(|metadata| metadata.is_file() && metadata.permissions().mode() & 0o111 != 0)
+}
+
+/// Number of entries shown at once, unless a backend is configured with a different `max_results`.
+const DEFAULT_MAX_RESULTS: usize = 200;
+
+fn default_max_results() -> usize {
+    DEFAULT_MAX_RESULTS
+}
+
+pub trait NewBackend: Sized {
     type Config: for<'de> Deserialize<'de>;
 
-    fn new(cc: &eframe::CreationContext<'_>, config: Self::Config) -> Self;
+    /// Creates the backend from its config. If this fails, the error is reported instead of
+    /// showing the launcher.
+    fn new(cc: &eframe::CreationContext<'_>, config: Self::Config) -> Result<Self>;
 }
 
 pub trait Backend<'entry> {
-    type Entry: Copy;
+    type Entry: Clone + EntryId;
 
-    fn entries(&'entry self, query: &str) -> Entries<Self::Entry>;
+    /// The entries matching `query`. An error is shown in place of the entries.
+    fn entries(&'entry self, query: &str) -> Result<Entries<Self::Entry>>;
     fn entry_contents(&self, ui: &mut egui::Ui, entry: Self::Entry);
 
+    /// The text the query is completed to when Tab is pressed with `entry` selected, if any. No
+    /// entries can be completed by default.
+    fn entry_completion(&self, _entry: Self::Entry) -> Option<String> {
+        None
+    }
+
     fn on_selected(&self, entry: Self::Entry);
+
+    /// Called instead of [`Backend::on_selected`] when an entry is selected with the alternate
+    /// action. Does the same as a regular selection by default.
+    fn on_selected_alternate(&self, entry: Self::Entry) {
+        self.on_selected(entry);
+    }
+
+    /// Reads the entries again, e.g. to show applications installed since the launcher was opened.
+    /// Called when a key bound to [`Action::Reload`](crate::keymap::Action::Reload) is pressed.
+    /// Does nothing by default.
+    fn reload(&mut self) {}
 }
 
 pub struct Entries<Entry> {
@@ -25,7 +105,115 @@ pub struct Entries<Entry> {
     pub entries: Vec<Entry>,
 }
 
+impl<Entry> Entries<Entry> {
+    /// The given entries, with a counter showing how many are visible out of `total`.
+    pub fn with_counter(entries: Vec<Entry>, total: usize) -> Self {
+        Self {
+            counter: Some(EntriesCounter {
+                visible: entries.len(),
+                total,
+            }),
+            entries,
+        }
+    }
+
+    /// The first `max_results` of the given entries, with a counter showing how many entries there
+    /// were before they were truncated out of `total`.
+    pub fn with_limit(mut entries: Vec<Entry>, max_results: usize, total: usize) -> Self {
+        let visible = entries.len();
+        entries.truncate(max_results);
+
+        Self {
+            counter: Some(EntriesCounter { visible, total }),
+            entries,
+        }
+    }
+}
+
 pub struct EntriesCounter {
     pub visible: usize,
     pub total: usize,
 }
+
+/// Splits `text` into runs of characters which are or aren't highlighted, where `indices` are the
+/// (sorted) indices of the highlighted characters.
+fn highlight_runs<'a>(text: &'a str, indices: &[usize]) -> Vec<(&'a str, bool)> {
+    let mut runs: Vec<(&str, bool)> = vec![];
+    let mut indices = indices.iter().peekable();
+    let mut run_start = 0;
+    let mut run_highlighted = false;
+
+    for (char_index, (byte_index, _)) in text.char_indices().enumerate() {
+        let highlighted = indices.next_if_eq(&&char_index).is_some();
+        if highlighted != run_highlighted && byte_index != run_start {
+            runs.push((&text[run_start..byte_index], run_highlighted));
+            run_start = byte_index;
+        }
+        run_highlighted = highlighted;
+    }
+    if run_start != text.len() {
+        runs.push((&text[run_start..], run_highlighted));
+    }
+
+    runs
+}
+
+/// Lays out `text` as a label would, but with the characters at `indices` highlighted.
+fn highlighted_text(ui: &egui::Ui, text: &str, indices: &[usize]) -> egui::text::LayoutJob {
+    let font_id = egui::TextStyle::Body.resolve(ui.style());
+    let color = ui.visuals().text_color();
+    let highlight_color = egui::Color32::from_rgb(0xf0, 0xb0, 0x40);
+
+    let mut job = egui::text::LayoutJob::default();
+    for (run, highlighted) in highlight_runs(text, indices) {
+        let format = egui::TextFormat {
+            font_id: font_id.clone(),
+            color: if highlighted { highlight_color } else { color },
+            ..Default::default()
+        };
+        job.append(run, 0.0, format);
+    }
+
+    job
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn entries_with_limit() {
+        let entries = Entries::with_limit(vec!["a", "b", "c", "d"], 2, 10);
+        assert_eq!(entries.entries, ["a", "b"]);
+        let counter = entries.counter.unwrap();
+        assert_eq!((counter.visible, counter.total), (4, 10));
+
+        // fewer entries than the limit are all kept
+        let entries = Entries::with_limit(vec!["a"], 2, 10);
+        assert_eq!(entries.entries, ["a"]);
+    }
+
+    #[test]
+    fn highlight_runs_segments() {
+        // nothing highlighted
+        assert_eq!(highlight_runs("Firefox", &[]), [("Firefox", false)]);
+        // highlighted at the start, middle and end
+        assert_eq!(
+            highlight_runs("Firefox", &[0, 4]),
+            [("F", true), ("ire", false), ("f", true), ("ox", false)]
+        );
+        assert_eq!(
+            highlight_runs("Firefox", &[5, 6]),
+            [("Firef", false), ("ox", true)]
+        );
+        // everything highlighted
+        assert_eq!(highlight_runs("ff", &[0, 1]), [("ff", true)]);
+        // indices are characters, not bytes
+        assert_eq!(
+            highlight_runs("Résumé", &[1, 5]),
+            [("R", false), ("é", true), ("sum", false), ("é", true)]
+        );
+        // empty text has no runs
+        assert_eq!(highlight_runs("", &[]), []);
+    }
+}
@@ -0,0 +1,211 @@
+use std::collections::HashSet;
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+use std::process::Command;
+
+use color_eyre::eyre::{Context, Result};
+use eframe::egui;
+use log::{error, info, trace, warn};
+use serde::Deserialize;
+
+use crate::backends::{default_max_results, is_executable, Backend, Entries, NewBackend};
+use crate::toffee::EntryId;
+
+/// Lists the executables in `$PATH`, running the selected executable without any arguments.
+pub struct Run {
+    programs: Vec<Program>,
+    terminal: Option<Vec<String>>,
+    max_results: usize,
+}
+
+#[derive(Deserialize)]
+pub struct Config {
+    /// Command to prefix programs with when they're run, e.g. `["xterm", "-e"]`. Programs are run
+    /// directly if unset.
+    terminal: Option<Vec<String>>,
+    /// Maximum number of programs to show. Defaults to 200.
+    #[serde(default = "default_max_results")]
+    max_results: usize,
+}
+
+impl NewBackend for Run {
+    type Config = Config;
+
+    fn new(_cc: &eframe::CreationContext<'_>, config: Self::Config) -> Result<Self> {
+        let directories = match env::var_os("PATH") {
+            Some(path) => env::split_paths(&path).collect(),
+            None => {
+                warn!("$PATH is not set, no programs to run");
+                vec![]
+            }
+        };
+
+        Ok(Self {
+            programs: scan_programs(&directories),
+            terminal: config.terminal,
+            max_results: config.max_results,
+        })
+    }
+}
+
+impl<'entry> Backend<'entry> for Run {
+    type Entry = &'entry Program;
+
+    fn entries(&'entry self, query: &str) -> Result<Entries<Self::Entry>> {
+        let query = query.to_lowercase();
+        let programs = self
+            .programs
+            .iter()
+            .filter(|program| program.name.to_lowercase().contains(&query))
+            .collect::<Vec<_>>();
+
+        Ok(Entries::with_limit(
+            programs,
+            self.max_results,
+            self.programs.len(),
+        ))
+    }
+
+    fn entry_contents(&self, ui: &mut egui::Ui, program: Self::Entry) {
+        ui.label(&program.name)
+            .on_hover_text(program.path.to_string_lossy());
+    }
+
+    fn entry_completion(&self, program: Self::Entry) -> Option<String> {
+        Some(program.name.clone())
+    }
+
+    fn on_selected(&self, program: Self::Entry) {
+        let terminal = self.terminal.as_deref().unwrap_or_default();
+        match program.run(terminal) {
+            Ok(_) => {}
+            Err(err) => {
+                error!("run failed - {}", err);
+            }
+        }
+    }
+}
+
+/// Finds the executable files in `directories`, sorted by name. Where several directories contain
+/// an executable with the same name, only the one in the earliest directory is kept (as it's the
+/// one that would be run).
+fn scan_programs(directories: &[PathBuf]) -> Vec<Program> {
+    let mut names = HashSet::new();
+    let mut programs = vec![];
+    for directory in directories {
+        trace!("reading programs from {:?}", directory);
+
+        let dir = match fs::read_dir(directory) {
+            Ok(dir) => dir,
+            Err(err) => {
+                warn!("couldn't read directory {:?} - {}", directory, err);
+                continue;
+            }
+        };
+
+        let paths = dir.flat_map(|dir_entry| match dir_entry {
+            Ok(dir_entry) => Some(dir_entry.path()),
+            Err(err) => {
+                warn!("reading directory entry failed - {}", err);
+                None
+            }
+        });
+        for path in paths.filter(|path| is_executable(path)) {
+            let Some(name) = path.file_name().and_then(|name| name.to_str()) else {
+                continue;
+            };
+
+            if names.insert(name.to_string()) {
+                programs.push(Program {
+                    name: name.to_string(),
+                    path,
+                });
+            }
+        }
+    }
+
+    programs.sort_by(|a, b| a.name.cmp(&b.name));
+    programs
+}
+
+pub struct Program {
+    name: String,
+    path: PathBuf,
+}
+
+impl EntryId for &Program {
+    fn id(&self) -> egui::Id {
+        egui::Id::new(&self.name)
+    }
+}
+
+impl Program {
+    /// Runs the program, prefixed by `prefix` (e.g. a terminal emulator) if it is non-empty.
+    fn run(&self, prefix: &[String]) -> Result<()> {
+        let mut command = match prefix {
+            [] => Command::new(&self.path),
+            [program, arguments @ ..] => {
+                let mut command = Command::new(program);
+                command.args(arguments).arg(&self.path);
+                command
+            }
+        };
+
+        info!(
+            "running {:?} with arguments {:?}",
+            command.get_program(),
+            command.get_args().collect::<Vec<_>>()
+        );
+
+        command.spawn().wrap_err("spawn failed")?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::os::unix::fs::PermissionsExt;
+    use std::path::Path;
+
+    use super::*;
+
+    fn create(directory: &Path, name: &str, mode: u32) {
+        let path = directory.join(name);
+        fs::write(&path, "#!/bin/sh\n").unwrap();
+        fs::set_permissions(&path, fs::Permissions::from_mode(mode)).unwrap();
+    }
+
+    #[test]
+    fn scan_programs_dedup() {
+        let first = tempfile::tempdir().unwrap();
+        create(first.path(), "vim", 0o755);
+        create(first.path(), "htop", 0o755);
+        create(first.path(), "README", 0o644);
+        fs::create_dir(first.path().join("subdirectory")).unwrap();
+
+        let second = tempfile::tempdir().unwrap();
+        create(second.path(), "vim", 0o755);
+        create(second.path(), "bash", 0o700);
+
+        let missing = first.path().join("missing");
+
+        let directories = [first.path().to_owned(), missing, second.path().to_owned()];
+        let programs = scan_programs(&directories);
+
+        // only executable files are listed, sorted by name
+        let names = programs
+            .iter()
+            .map(|program| program.name.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(names, ["bash", "htop", "vim"]);
+
+        // the first directory containing a program wins
+        let vim = programs
+            .iter()
+            .find(|program| program.name == "vim")
+            .unwrap();
+        assert_eq!(vim.path, first.path().join("vim"));
+    }
+}
@@ -0,0 +1,278 @@
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+use color_eyre::eyre::{bail, eyre, Context, Result};
+use eframe::egui;
+use log::{error, info};
+use serde::Deserialize;
+
+use crate::backends::{default_max_results, Backend, Entries, NewBackend};
+use crate::toffee::EntryId;
+
+/// Lists the lines printed by a command (like dmenu's input), passing the selected line to another
+/// command on its standard input.
+pub struct Script {
+    /// The lines printed by the command, or why it failed.
+    lines: Result<Vec<Line>>,
+    on_select: Vec<String>,
+    max_results: usize,
+}
+
+#[derive(Deserialize)]
+pub struct Config {
+    /// Command which prints the entries, one per line, e.g. `["ls", "/usr/share/backgrounds"]`.
+    /// It's run once, when the mode starts.
+    command: Vec<String>,
+    /// Command which is given the selected entry on its standard input, e.g. `["wl-copy"]`.
+    on_select: Vec<String>,
+    /// Maximum number of entries to show. Defaults to 200.
+    #[serde(default = "default_max_results")]
+    max_results: usize,
+}
+
+impl NewBackend for Script {
+    type Config = Config;
+
+    fn new(_cc: &eframe::CreationContext<'_>, config: Self::Config) -> Result<Self> {
+        Ok(Self::with_runner(&ProcessRunner, config))
+    }
+}
+
+impl Script {
+    /// Runs the command with `runner` to list the lines.
+    fn with_runner(runner: &impl Runner, config: Config) -> Self {
+        let lines = runner
+            .output(&config.command)
+            .map(|output| parse_lines(&output));
+
+        Self {
+            lines,
+            on_select: config.on_select,
+            max_results: config.max_results,
+        }
+    }
+}
+
+impl<'entry> Backend<'entry> for Script {
+    type Entry = &'entry Line;
+
+    fn entries(&'entry self, query: &str) -> Result<Entries<Self::Entry>> {
+        let all_lines = match &self.lines {
+            Ok(lines) => lines,
+            Err(err) => bail!("script command failed - {:#}", err),
+        };
+
+        let query = query.to_lowercase();
+        let lines = all_lines
+            .iter()
+            .filter(|line| line.text.to_lowercase().contains(&query))
+            .collect::<Vec<_>>();
+
+        Ok(Entries::with_limit(
+            lines,
+            self.max_results,
+            all_lines.len(),
+        ))
+    }
+
+    fn entry_contents(&self, ui: &mut egui::Ui, line: Self::Entry) {
+        ui.label(&line.text);
+    }
+
+    fn entry_completion(&self, line: Self::Entry) -> Option<String> {
+        Some(line.text.clone())
+    }
+
+    fn on_selected(&self, line: Self::Entry) {
+        if let Err(err) = select(&ProcessRunner, &self.on_select, line) {
+            error!("select command failed - {}", err);
+        }
+    }
+}
+
+pub struct Line {
+    /// Position in the command's output (ignoring blank lines), so repeated lines are distinct.
+    index: usize,
+    text: String,
+}
+
+impl EntryId for &Line {
+    fn id(&self) -> egui::Id {
+        egui::Id::new((self.index, &self.text))
+    }
+}
+
+/// Runs the script's commands, so they can be replaced in tests.
+trait Runner {
+    /// Runs `command` to completion, returning what it printed to its standard output.
+    fn output(&self, command: &[String]) -> Result<String>;
+
+    /// Starts `command`, writing `input` to its standard input.
+    fn run_with_input(&self, command: &[String], input: &str) -> Result<()>;
+}
+
+struct ProcessRunner;
+
+impl ProcessRunner {
+    fn command(command: &[String]) -> Result<Command> {
+        let [program, arguments @ ..] = command else {
+            bail!("command should not be empty");
+        };
+
+        let mut command = Command::new(program);
+        command.args(arguments);
+        Ok(command)
+    }
+}
+
+impl Runner for ProcessRunner {
+    fn output(&self, command: &[String]) -> Result<String> {
+        info!("running {:?}", command);
+        let output = Self::command(command)?
+            .stderr(Stdio::inherit())
+            .output()
+            .wrap_err("run failed")?;
+        if !output.status.success() {
+            return Err(eyre!("exited with {}", output.status));
+        }
+
+        Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+    }
+
+    fn run_with_input(&self, command: &[String], input: &str) -> Result<()> {
+        info!("running {:?} with input {:?}", command, input);
+        let mut child = Self::command(command)?
+            .stdin(Stdio::piped())
+            .spawn()
+            .wrap_err("spawn failed")?;
+
+        // the child's standard input is closed when it's dropped, so the command sees the end of
+        // the input
+        let mut stdin = child.stdin.take().expect("stdin should be piped");
+        stdin
+            .write_all(input.as_bytes())
+            .wrap_err("writing input failed")?;
+
+        Ok(())
+    }
+}
+
+/// Splits a command's output into entries, one per line, skipping blank lines.
+fn parse_lines(output: &str) -> Vec<Line> {
+    output
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .enumerate()
+        .map(|(index, line)| Line {
+            index,
+            text: line.to_string(),
+        })
+        .collect()
+}
+
+/// Runs the `on_select` command with the selected `line` (followed by a newline) as its input.
+fn select(runner: &impl Runner, on_select: &[String], line: &Line) -> Result<()> {
+    runner.run_with_input(on_select, &format!("{}\n", line.text))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+
+    use super::*;
+
+    /// Records the commands it's asked to run, rather than running them. Commands run for their
+    /// output print `output`, or fail if it's `None`.
+    #[derive(Default)]
+    struct FakeRunner {
+        output: Option<String>,
+        runs: RefCell<Vec<(Vec<String>, String)>>,
+    }
+
+    impl FakeRunner {
+        fn with_output(output: &str) -> Self {
+            Self {
+                output: Some(output.to_string()),
+                ..Default::default()
+            }
+        }
+    }
+
+    impl Runner for FakeRunner {
+        fn output(&self, _command: &[String]) -> Result<String> {
+            self.output.clone().ok_or_else(|| eyre!("exited with 1"))
+        }
+
+        fn run_with_input(&self, command: &[String], input: &str) -> Result<()> {
+            self.runs
+                .borrow_mut()
+                .push((command.to_vec(), input.to_string()));
+            Ok(())
+        }
+    }
+
+    fn texts(lines: &[Line]) -> Vec<&str> {
+        lines.iter().map(|line| line.text.as_str()).collect()
+    }
+
+    #[test]
+    fn parse_lines_skips_blank() {
+        assert_eq!(
+            texts(&parse_lines("alpha\n\nbeta gamma\n  \r\ndelta\r\n")),
+            ["alpha", "beta gamma", "delta"]
+        );
+        assert_eq!(texts(&parse_lines("")), [] as [&str; 0]);
+    }
+
+    #[test]
+    fn select_pipes_line() {
+        let runner = FakeRunner::default();
+        let on_select = ["wl-copy".to_string()];
+        let line = Line {
+            index: 0,
+            text: "beta gamma".to_string(),
+        };
+
+        select(&runner, &on_select, &line).unwrap();
+        assert_eq!(
+            runner.runs.into_inner(),
+            [(vec!["wl-copy".to_string()], "beta gamma\n".to_string())]
+        );
+    }
+
+    fn config(command: &str) -> Config {
+        Config {
+            command: vec![command.to_string()],
+            on_select: vec!["wl-copy".to_string()],
+            max_results: 200,
+        }
+    }
+
+    fn entry_texts(script: &Script, query: &str) -> Vec<String> {
+        let entries = script.entries(query).unwrap().entries;
+        entries.iter().map(|line| line.text.clone()).collect()
+    }
+
+    #[test]
+    fn script_lists_output() {
+        let runner = FakeRunner::with_output("Alpha\nbeta\n\nalphabet\n");
+        let script = Script::with_runner(&runner, config("list"));
+        assert_eq!(entry_texts(&script, ""), ["Alpha", "beta", "alphabet"]);
+        assert_eq!(entry_texts(&script, "alp"), ["Alpha", "alphabet"]);
+    }
+
+    #[test]
+    fn script_command_failed() {
+        let script = Script::with_runner(&FakeRunner::default(), config("list"));
+        let err = script.entries("").err().unwrap();
+        assert_eq!(format!("{err}"), "script command failed - exited with 1");
+    }
+
+    #[test]
+    fn line_ids_distinct() {
+        let runner = FakeRunner::with_output("same\nsame\n");
+        let script = Script::with_runner(&runner, config("list"));
+        let lines = script.lines.as_ref().unwrap();
+        assert_ne!((&lines[0]).id(), (&lines[1]).id());
+    }
+}
@@ -0,0 +1,42 @@
+//! A backend for tests which don't depend on what the entries are.
+
+use color_eyre::eyre::{eyre, Context, Result};
+use eframe::egui;
+
+use crate::backends::{Backend, Entries, NewBackend};
+use crate::toffee::EntryId;
+
+#[derive(Clone)]
+pub struct TestEntry;
+
+impl EntryId for TestEntry {
+    fn id(&self) -> egui::Id {
+        egui::Id::new("entry")
+    }
+}
+
+/// Lists a single entry, unless the query is `fail`.
+pub struct TestBackend;
+
+impl<'entry> Backend<'entry> for TestBackend {
+    type Entry = TestEntry;
+
+    fn entries(&'entry self, query: &str) -> Result<Entries<Self::Entry>> {
+        if query == "fail" {
+            return Err(eyre!("no entries")).wrap_err("listing failed");
+        }
+        Ok(Entries::with_counter(vec![TestEntry], 1))
+    }
+
+    fn entry_contents(&self, _ui: &mut egui::Ui, _entry: Self::Entry) {}
+
+    fn on_selected(&self, _entry: Self::Entry) {}
+}
+
+impl NewBackend for TestBackend {
+    type Config = ();
+
+    fn new(_cc: &eframe::CreationContext<'_>, _config: Self::Config) -> Result<Self> {
+        Ok(Self)
+    }
+}
@@ -0,0 +1,89 @@
+use std::collections::HashMap;
+use std::fs;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+
+use color_eyre::eyre::{Context, Result};
+
+/// Counts how many times each entry (identified by a path, e.g. of its desktop file) has been
+/// selected, stored as JSON so the counts persist between runs.
+pub struct Usage {
+    path: PathBuf,
+    counts: HashMap<PathBuf, u64>,
+}
+
+impl Usage {
+    /// No usage, stored in `path` once saved.
+    pub fn new(path: PathBuf) -> Self {
+        Self {
+            path,
+            counts: HashMap::new(),
+        }
+    }
+
+    /// Loads the usage stored in `path`, which is empty if the file doesn't exist yet.
+    pub fn load(path: PathBuf) -> Result<Self> {
+        let contents = match fs::read_to_string(&path) {
+            Ok(contents) => contents,
+            Err(err) if err.kind() == ErrorKind::NotFound => return Ok(Self::new(path)),
+            Err(err) => {
+                return Err(err).wrap_err_with(|| format!("failed to read usage file {path:?}"))
+            }
+        };
+        let counts = serde_json::from_str(&contents)
+            .wrap_err_with(|| format!("failed to parse usage file {path:?}"))?;
+
+        Ok(Self { path, counts })
+    }
+
+    pub fn save(&self) -> Result<()> {
+        let path = &self.path;
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)
+                .wrap_err_with(|| format!("failed to create directory {parent:?}"))?;
+        }
+
+        let contents = serde_json::to_string(&self.counts).wrap_err("failed to serialize usage")?;
+        fs::write(path, contents)
+            .wrap_err_with(|| format!("failed to write usage file {path:?}"))?;
+
+        Ok(())
+    }
+
+    pub fn counts(&self) -> &HashMap<PathBuf, u64> {
+        &self.counts
+    }
+
+    pub fn increment(&mut self, key: &Path) {
+        *self.counts.entry(key.to_owned()).or_default() += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        let directory = tempfile::tempdir().unwrap();
+        let path = directory.path().join("toffee/usage.json");
+
+        // a missing file is no usage
+        let mut usage = Usage::load(path.clone()).unwrap();
+        assert!(usage.counts().is_empty());
+
+        usage.increment(Path::new("/firefox.desktop"));
+        usage.increment(Path::new("/firefox.desktop"));
+        usage.increment(Path::new("/htop.desktop"));
+        usage.save().unwrap();
+
+        let usage = Usage::load(path).unwrap();
+        assert_eq!(
+            usage.counts(),
+            &HashMap::from([
+                (PathBuf::from("/firefox.desktop"), 2),
+                (PathBuf::from("/htop.desktop"), 1),
+            ])
+        );
+    }
+}
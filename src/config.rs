@@ -1,9 +1,13 @@
+use std::path::PathBuf;
 use std::str::FromStr;
 
 use color_eyre::eyre::{eyre, Context, Report, Result};
 use serde::Deserialize;
 use toml::Table;
 
+use crate::keymap::Keymap;
+use crate::theme::Theme;
+
 #[derive(Deserialize, Debug)]
 pub struct Config {
     pub toffee: ToffeeConfig,
@@ -13,7 +17,37 @@ pub struct Config {
 #[derive(Deserialize, Debug)]
 pub struct ToffeeConfig {
     pub debug: Option<bool>,
-    pub initial_size: Option<(usize, usize)>,
+    /// Initial width and height of the window. Defaults to 500x200.
+    #[serde(default = "default_initial_size")]
+    pub initial_size: (usize, usize),
+    /// Initial position of the window's top left corner. Left to the window manager if unset.
+    pub initial_position: Option<(i32, i32)>,
+    /// Scale factor of the UI (i.e. egui's pixels per point), clamped to between 0.5 and 4.0. The
+    /// display's scale factor is used if unset.
+    scale: Option<f32>,
+    /// Number of entries to move by with PageUp/PageDown.
+    pub page_size: Option<usize>,
+    pub theme: Option<Theme>,
+    /// Font file to use as the proportional font, e.g. `/usr/share/fonts/TTF/monof55.ttf`. egui's
+    /// built-in font is used if unset.
+    pub font: Option<PathBuf>,
+    /// Keys bound to each action, e.g. `down = ["Down", "Ctrl+N"]`. Unbound actions keep their
+    /// default key.
+    #[serde(default)]
+    pub keybindings: Keymap,
+}
+
+fn default_initial_size() -> (usize, usize) {
+    (500, 200)
+}
+
+impl ToffeeConfig {
+    /// The configured scale factor, if any. `nan` is ignored, as if the scale wasn't set.
+    pub fn scale(&self) -> Option<f32> {
+        self.scale
+            .filter(|scale| !scale.is_nan())
+            .map(|scale| scale.clamp(0.5, 4.0))
+    }
 }
 
 impl FromStr for Config {
@@ -63,4 +97,46 @@ pub struct ModeConfig<C = Table> {
 pub struct MetaConfig {
     pub name: Option<String>,
     pub backend: String,
+    /// Shown in the query input while it's empty.
+    pub prompt: Option<String>,
+    /// Keys bound to each action in this mode, replacing the global keybindings for those actions.
+    #[serde(default)]
+    pub keybindings: Keymap,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn window_defaults() {
+        let config = "[toffee]\n".parse::<Config>().unwrap();
+        assert_eq!(config.toffee.initial_size, (500, 200));
+        assert_eq!(config.toffee.initial_position, None);
+        assert_eq!(config.toffee.scale(), None);
+    }
+
+    #[test]
+    fn window_size_and_position() {
+        let config = "[toffee]\ninitial_size = [800, 300]\ninitial_position = [-10, 40]\n"
+            .parse::<Config>()
+            .unwrap();
+        assert_eq!(config.toffee.initial_size, (800, 300));
+        assert_eq!(config.toffee.initial_position, Some((-10, 40)));
+    }
+
+    #[test]
+    fn scale() {
+        let config = "[toffee]\nscale = 2.0\n".parse::<Config>().unwrap();
+        assert_eq!(config.toffee.scale(), Some(2.0));
+
+        // out of range scales are clamped...
+        let config = "[toffee]\nscale = 0.1\n".parse::<Config>().unwrap();
+        assert_eq!(config.toffee.scale(), Some(0.5));
+        let config = "[toffee]\nscale = 10\n".parse::<Config>().unwrap();
+        assert_eq!(config.toffee.scale(), Some(4.0));
+        // ... and NaN is ignored
+        let config = "[toffee]\nscale = nan\n".parse::<Config>().unwrap();
+        assert_eq!(config.toffee.scale(), None);
+    }
 }
@@ -0,0 +1,318 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
+
+use color_eyre::eyre::{bail, eyre, Report, Result};
+use eframe::egui;
+use serde::Deserialize;
+
+/// Something the launcher does in response to a key press.
+#[derive(Deserialize, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum Action {
+    Up,
+    Down,
+    PageUp,
+    PageDown,
+    Home,
+    End,
+    Select,
+    Cancel,
+    /// Reads the mode's entries again.
+    Reload,
+}
+
+impl Action {
+    fn default_key(self) -> egui::Key {
+        match self {
+            Action::Up => egui::Key::ArrowUp,
+            Action::Down => egui::Key::ArrowDown,
+            Action::PageUp => egui::Key::PageUp,
+            Action::PageDown => egui::Key::PageDown,
+            Action::Home => egui::Key::Home,
+            Action::End => egui::Key::End,
+            Action::Select => egui::Key::Enter,
+            Action::Cancel => egui::Key::Escape,
+            Action::Reload => egui::Key::F5,
+        }
+    }
+}
+
+/// A key, pressed while holding some modifiers. Parsed from strings like `Ctrl+N` or `Down`, where
+/// the key is named as in [`egui::Key::from_name`].
+#[derive(Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(try_from = "String")]
+pub struct KeyBinding {
+    pub modifiers: egui::Modifiers,
+    pub key: egui::Key,
+}
+
+impl KeyBinding {
+    fn new(key: egui::Key) -> Self {
+        Self {
+            modifiers: egui::Modifiers::NONE,
+            key,
+        }
+    }
+
+    /// Whether the key was pressed this frame while (at least) the binding's modifiers were held,
+    /// without consuming the key press.
+    fn pressed(&self, i: &egui::InputState) -> bool {
+        let held = i.modifiers;
+        let wanted = self.modifiers;
+        i.key_pressed(self.key)
+            && (held.alt || !wanted.alt)
+            && (held.ctrl || !wanted.ctrl)
+            && (held.shift || !wanted.shift)
+            && (held.command || !wanted.command)
+            && (held.mac_cmd || !wanted.mac_cmd)
+    }
+
+    /// Whether the key was pressed this frame while holding the binding's modifiers, consuming the
+    /// key press. Without Ctrl, Alt or Command, the text typed by the key (e.g. `j`) is consumed
+    /// too.
+    fn consume(&self, i: &mut egui::InputState) -> bool {
+        if !i.consume_key(self.modifiers, self.key) {
+            return false;
+        }
+
+        let modifiers = self.modifiers;
+        if !(modifiers.ctrl || modifiers.alt || modifiers.command || modifiers.mac_cmd) {
+            i.events.retain(
+                |event| !matches!(event, egui::Event::Text(text) if types_text(self.key, text)),
+            );
+        }
+        true
+    }
+}
+
+/// Whether pressing `key` types `text`, e.g. `J` types `j` (or `J`, with Shift).
+fn types_text(key: egui::Key, text: &str) -> bool {
+    let mut chars = text.chars();
+    match (chars.next(), chars.next()) {
+        (Some(' '), None) => key == egui::Key::Space,
+        (Some(c), None) => egui::Key::from_name(&c.to_uppercase().to_string()) == Some(key),
+        _ => false,
+    }
+}
+
+impl FromStr for KeyBinding {
+    type Err = Report;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let mut parts = s.split('+').map(str::trim).collect::<Vec<_>>();
+        let key_name = parts.pop().unwrap_or_default();
+
+        let mut modifiers = egui::Modifiers::NONE;
+        for modifier in parts {
+            match modifier.to_ascii_lowercase().as_str() {
+                "ctrl" | "control" => modifiers.ctrl = true,
+                "alt" => modifiers.alt = true,
+                "shift" => modifiers.shift = true,
+                "cmd" | "command" => modifiers.command = true,
+                _ => bail!("unknown modifier {modifier:?} in key binding {s:?}"),
+            }
+        }
+
+        // letters can be given in either case, e.g. `Ctrl+n`
+        let key_name = if key_name.len() == 1 {
+            key_name.to_ascii_uppercase()
+        } else {
+            key_name.to_string()
+        };
+        let key = egui::Key::from_name(&key_name)
+            .ok_or_else(|| eyre!("unknown key {key_name:?} in key binding {s:?}"))?;
+
+        Ok(Self { modifiers, key })
+    }
+}
+
+impl TryFrom<String> for KeyBinding {
+    type Error = Report;
+
+    fn try_from(s: String) -> Result<Self> {
+        s.parse()
+    }
+}
+
+impl fmt::Display for KeyBinding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (held, name) in [
+            (self.modifiers.ctrl, "Ctrl"),
+            (self.modifiers.command, "Cmd"),
+            (self.modifiers.alt, "Alt"),
+            (self.modifiers.shift, "Shift"),
+        ] {
+            if held {
+                write!(f, "{name}+")?;
+            }
+        }
+        write!(f, "{}", self.key.name())
+    }
+}
+
+/// The keys bound to each [`Action`], e.g. from a `keybindings` table like
+/// `down = ["Down", "Ctrl+N"]`. Actions which aren't bound keep their default key.
+#[derive(Deserialize, Clone, Debug, Default, PartialEq)]
+#[serde(transparent)]
+pub struct Keymap {
+    bindings: HashMap<Action, Vec<KeyBinding>>,
+}
+
+impl Keymap {
+    /// Returns the keys bound to `action`.
+    pub fn bindings(&self, action: Action) -> Vec<KeyBinding> {
+        match self.bindings.get(&action) {
+            Some(bindings) => bindings.clone(),
+            None => vec![KeyBinding::new(action.default_key())],
+        }
+    }
+
+    /// Rebinds the actions bound in `other`, e.g. to apply a mode's keybindings over the global
+    /// keybindings.
+    pub fn extend(&mut self, other: Keymap) {
+        self.bindings.extend(other.bindings);
+    }
+
+    /// Whether a key bound to `action` was pressed, consuming the key press (and any text it typed)
+    /// so it isn't also handled by the query input.
+    pub fn consume(&self, i: &mut egui::InputState, action: Action) -> bool {
+        self.bindings(action)
+            .into_iter()
+            .any(|binding| binding.consume(i))
+    }
+
+    /// Whether a key bound to `action` was pressed, ignoring any extra modifiers (so e.g.
+    /// Shift+Enter still selects).
+    pub fn pressed(&self, i: &egui::InputState, action: Action) -> bool {
+        self.bindings(action)
+            .iter()
+            .any(|binding| binding.pressed(i))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_with_modifier() {
+        let binding = "Ctrl+N".parse::<KeyBinding>().unwrap();
+        assert_eq!(binding.key, egui::Key::N);
+        assert_eq!(
+            binding.modifiers,
+            egui::Modifiers {
+                ctrl: true,
+                ..egui::Modifiers::NONE
+            }
+        );
+        assert_eq!(binding.to_string(), "Ctrl+N");
+
+        // modifiers and letters can be given in any case
+        assert_eq!(
+            "ctrl+shift+n".parse::<KeyBinding>().unwrap().to_string(),
+            "Ctrl+Shift+N"
+        );
+    }
+
+    #[test]
+    fn parse_without_modifier() {
+        assert_eq!(
+            "j".parse::<KeyBinding>().unwrap(),
+            KeyBinding::new(egui::Key::J)
+        );
+        assert_eq!(
+            "Down".parse::<KeyBinding>().unwrap(),
+            KeyBinding::new(egui::Key::ArrowDown)
+        );
+    }
+
+    #[test]
+    fn parse_invalid() {
+        assert!("Hyper+N".parse::<KeyBinding>().is_err());
+        assert!("Ctrl+".parse::<KeyBinding>().is_err());
+        assert!("Ctrl+Nope".parse::<KeyBinding>().is_err());
+        assert!("".parse::<KeyBinding>().is_err());
+    }
+
+    #[test]
+    fn defaults() {
+        let keymap = Keymap::default();
+        assert_eq!(
+            keymap.bindings(Action::Up),
+            [KeyBinding::new(egui::Key::ArrowUp)]
+        );
+        assert_eq!(
+            keymap.bindings(Action::Select),
+            [KeyBinding::new(egui::Key::Enter)]
+        );
+        assert_eq!(
+            keymap.bindings(Action::Cancel),
+            [KeyBinding::new(egui::Key::Escape)]
+        );
+        assert_eq!(
+            keymap.bindings(Action::Reload),
+            [KeyBinding::new(egui::Key::F5)]
+        );
+    }
+
+    #[test]
+    fn deserialize_and_extend() {
+        let mut keymap: Keymap =
+            toml::from_str("down = [\"Down\", \"Ctrl+N\"]\nup = []\n").unwrap();
+        assert_eq!(
+            keymap.bindings(Action::Down),
+            ["Down".parse().unwrap(), "Ctrl+N".parse().unwrap()]
+        );
+        // actions can be unbound...
+        assert_eq!(keymap.bindings(Action::Up), []);
+        // ... and unconfigured actions keep their default
+        assert_eq!(
+            keymap.bindings(Action::PageDown),
+            [KeyBinding::new(egui::Key::PageDown)]
+        );
+
+        // a mode's keybindings replace the global keybindings for the same action
+        keymap.extend(toml::from_str("down = [\"j\"]\n").unwrap());
+        assert_eq!(keymap.bindings(Action::Down), ["j".parse().unwrap()]);
+        assert_eq!(keymap.bindings(Action::Up), []);
+
+        assert!(toml::from_str::<Keymap>("sideways = [\"Left\"]\n").is_err());
+        assert!(toml::from_str::<Keymap>("down = [\"Ctrl+Nope\"]\n").is_err());
+    }
+
+    fn key_press(key: egui::Key, modifiers: egui::Modifiers) -> egui::Event {
+        egui::Event::Key {
+            key,
+            pressed: true,
+            physical_key: None,
+            repeat: false,
+            modifiers,
+        }
+    }
+
+    #[test]
+    fn consume_typed_text() {
+        let keymap: Keymap = toml::from_str("down = [\"j\"]\nup = [\"Ctrl+K\"]\n").unwrap();
+        let text = |text: &str| egui::Event::Text(text.to_string());
+
+        // a key bound without modifiers doesn't also type into the query...
+        let mut i = egui::InputState::default();
+        i.events = vec![
+            key_press(egui::Key::J, egui::Modifiers::NONE),
+            text("j"),
+            text("x"),
+        ];
+        assert!(keymap.consume(&mut i, Action::Down));
+        assert_eq!(i.events, [text("x")]);
+
+        // ... but other text is left alone when a key with modifiers is consumed
+        i.events = vec![key_press(egui::Key::K, egui::Modifiers::CTRL), text("k")];
+        assert!(keymap.consume(&mut i, Action::Up));
+        assert_eq!(i.events, [text("k")]);
+
+        i.events = vec![text("j")];
+        assert!(!keymap.consume(&mut i, Action::Down));
+        assert_eq!(i.events, [text("j")]);
+    }
+}
@@ -1,100 +1,162 @@
 mod backends;
 mod config;
+mod keymap;
+mod theme;
 mod toffee;
 
+use std::cell::RefCell;
+use std::path::Path;
+use std::rc::Rc;
 use std::{env, fs};
 
 use color_eyre::eyre::{bail, eyre, Context, Result};
 use egui::ViewportBuilder;
-use log::info;
+use log::{info, warn};
 
-use crate::backends::{Backend, NewBackend};
+use crate::backends::{Backend, Entries, NewBackend};
 use crate::config::{Config, ToffeeConfig};
-use crate::toffee::{Toffee, ToffeeData};
+use crate::toffee::{EntryId, Toffee, ToffeeData};
 
 struct Mode<B: for<'entry> Backend<'entry>> {
     config: ToffeeConfig,
     name: String,
+    prompt: Option<String>,
     backend: B,
     query: String,
 }
 
 impl<B: for<'entry> Backend<'entry> + NewBackend + 'static> Mode<B> {
     fn start(config: Config, name: String) -> Result<()> {
-        let (initial_width, initial_height) = config.toffee.initial_size.unwrap_or((500, 200));
+        let (initial_width, initial_height) = config.toffee.initial_size;
+
+        let mut viewport = ViewportBuilder::default().with_inner_size(egui::emath::Vec2::new(
+            initial_width as f32,
+            initial_height as f32,
+        ));
+        if let Some((x, y)) = config.toffee.initial_position {
+            viewport = viewport.with_position(egui::emath::Pos2::new(x as f32, y as f32));
+        }
 
         let native_options = eframe::NativeOptions {
-            viewport: ViewportBuilder::default().with_inner_size(egui::emath::Vec2::new(
-                initial_width as f32,
-                initial_height as f32,
-            )),
+            viewport,
             ..eframe::NativeOptions::default()
         };
 
+        // the app is created once the window is, so an error creating it is passed back out here
+        let error = Rc::new(RefCell::new(None));
+        let creation_error = error.clone();
         eframe::run_native(
             "toffee",
             native_options,
-            Box::new(|cc| Box::new(Self::new(cc, config, name))),
+            Box::new(move |cc| match Self::new(cc, config, name) {
+                Ok(mode) => Box::new(mode),
+                Err(err) => {
+                    *creation_error.borrow_mut() = Some(err);
+                    cc.egui_ctx.send_viewport_cmd(egui::ViewportCommand::Close);
+                    Box::new(Closing)
+                }
+            }),
         )
-        .map_err(|_| eyre!("app run_native failed"))
+        .map_err(|_| eyre!("app run_native failed"))?;
+
+        match error.take() {
+            Some(err) => Err(err),
+            None => Ok(()),
+        }
     }
 
-    fn new(cc: &eframe::CreationContext<'_>, config: Config, name: String) -> Self {
+    fn new(cc: &eframe::CreationContext<'_>, config: Config, name: String) -> Result<Self> {
         let ctx = &cc.egui_ctx;
         egui_extras::install_image_loaders(ctx);
 
-        // scale the ui up a bit
-        // ctx.set_pixels_per_point(1.5);
-
-        // add monofur, use as default proportional font
-        // TODO: can we do this without explicit paths and using the system's fonts?
-        // let mut fonts = egui::FontDefinitions::default();
-        // fonts.font_data.insert(
-        //     "monofur".to_owned(),
-        //     egui::FontData::from_static(include_bytes!("/usr/share/fonts/TTF/monof55.ttf")),
-        // );
-        // fonts
-        //     .families
-        //     .get_mut(&egui::FontFamily::Proportional)
-        //     .unwrap()
-        //     .insert(0, "monofur".to_owned());
-        // ctx.set_fonts(fonts);
-
         // start the backend
-        // TODO: error handling
-        let (toffee_config, mode_config) = config.split(&name).unwrap();
+        let (mut toffee_config, mode_config) = config.split(&name)?;
+        if let Some(scale) = toffee_config.scale() {
+            ctx.set_pixels_per_point(scale);
+        }
+        toffee_config
+            .keybindings
+            .extend(mode_config.meta.keybindings);
 
-        Self {
+        // use the configured font as the default proportional font
+        if let Some(font) = toffee_config.font.as_deref().and_then(read_font) {
+            let mut fonts = egui::FontDefinitions::default();
+            fonts.font_data.insert("font".to_owned(), font);
+            fonts
+                .families
+                .entry(egui::FontFamily::Proportional)
+                .or_default()
+                .insert(0, "font".to_owned());
+            ctx.set_fonts(fonts);
+        }
+
+        let backend = B::new(cc, mode_config.backend)
+            .wrap_err_with(|| format!("failed to start mode {name}"))?;
+
+        Ok(Self {
             config: toffee_config,
             name,
-            backend: B::new(cc, mode_config.backend),
+            prompt: mode_config.meta.prompt,
+            backend,
             query: String::new(),
-        }
+        })
     }
 }
 
+/// Shown in place of a mode which couldn't be created, until the window closes.
+struct Closing;
+
+impl eframe::App for Closing {
+    fn update(&mut self, _ctx: &egui::Context, _frame: &mut eframe::Frame) {}
+}
+
 impl<B: for<'entry> Backend<'entry>> eframe::App for Mode<B> {
     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
         let toffee = |ui: &mut egui::Ui| {
             let entries = self.backend.entries(&self.query);
+            let toffee_data = toffee_data(&self.name, entries);
 
-            let toffee_data = ToffeeData {
-                mode: &self.name,
-                counter: entries.counter.map(|c| (c.visible, c.total)),
-                entries: entries.entries,
-            };
+            let mut toffee = Toffee::new("toffee", toffee_data, &mut self.query);
+            if let Some(page_size) = self.config.page_size {
+                toffee = toffee.page_size(page_size);
+            }
+            if let Some(theme) = self.config.theme {
+                toffee = toffee.theme(theme);
+            }
+            if let Some(prompt) = &self.prompt {
+                toffee = toffee.prompt(prompt);
+            }
+            toffee = toffee.keymap(self.config.keybindings.clone());
+            let toffee = toffee.show(
+                ui,
+                |ui, entry| self.backend.entry_contents(ui, entry),
+                |entry| self.backend.entry_completion(entry),
+            );
 
-            let toffee = Toffee::new("toffee", toffee_data, &mut self.query)
-                .show(ui, |ui, entry| self.backend.entry_contents(ui, entry));
+            if toffee.escaped {
+                ui.ctx().send_viewport_cmd(egui::ViewportCommand::Close);
+                return false;
+            }
 
             if let Some(selected_entry) = toffee.selected_entry {
-                self.backend.on_selected(selected_entry);
+                if toffee.alternate {
+                    self.backend.on_selected_alternate(selected_entry);
+                } else {
+                    self.backend.on_selected(selected_entry);
+                }
             }
+
+            toffee.reload
         };
 
-        egui::CentralPanel::default()
+        let reload = egui::CentralPanel::default()
             .frame(egui::Frame::none())
-            .show(ctx, toffee);
+            .show(ctx, toffee)
+            .inner;
+        // the entries borrow the backend while they're shown, so it's reloaded afterwards
+        if reload {
+            self.backend.reload();
+        }
         if self.config.debug.unwrap_or(false) {
             egui::SidePanel::right("right")
                 .resizable(false)
@@ -105,6 +167,52 @@ impl<B: for<'entry> Backend<'entry>> eframe::App for Mode<B> {
     }
 }
 
+/// Builds the data for the launcher from the backend's entries, showing the error in place of the
+/// entries if they couldn't be listed.
+fn toffee_data<Entry: Clone + EntryId>(
+    mode: &str,
+    entries: Result<Entries<Entry>>,
+) -> ToffeeData<'_, Entry> {
+    let (counter, entries, error) = match entries {
+        Ok(entries) => (entries.counter, entries.entries, None),
+        Err(err) => (None, vec![], Some(format!("{err:#}"))),
+    };
+
+    ToffeeData {
+        mode,
+        prompt: None,
+        counter: counter.map(|c| (c.visible, c.total)),
+        entries,
+        error,
+    }
+}
+
+/// Reads the font file at `path`, or returns `None` (so egui's built-in fonts are used) if it can't
+/// be read or isn't a valid font.
+fn read_font(path: &Path) -> Option<egui::FontData> {
+    match fs::read(path) {
+        Ok(font) => match ab_glyph::FontRef::try_from_slice(&font) {
+            Ok(_) => Some(egui::FontData::from_owned(font)),
+            Err(err) => {
+                warn!(
+                    "using the default font, {} isn't a valid font - {}",
+                    path.display(),
+                    err
+                );
+                None
+            }
+        },
+        Err(err) => {
+            warn!(
+                "using the default font, couldn't read {} - {}",
+                path.display(),
+                err
+            );
+            None
+        }
+    }
+}
+
 fn main() -> Result<()> {
     color_eyre::install()?;
     env_logger::init();
@@ -121,6 +229,60 @@ fn main() -> Result<()> {
     info!("launching mode {mode} with backend {backend}");
     match backend.as_str() {
         "drun" => Mode::<backends::DRun>::start(config, mode),
+        "autostart" => Mode::<backends::Autostart>::start(config, mode),
+        "run" => Mode::<backends::Run>::start(config, mode),
+        "script" => Mode::<backends::Script>::start(config, mode),
         _ => bail!("unknown backend {backend}"),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backends::test::TestBackend;
+
+    #[test]
+    fn test_backend_starts() {
+        // any backend implementing the traits can be started as a mode
+        let _start: fn(Config, String) -> Result<()> = Mode::<TestBackend>::start;
+    }
+
+    #[test]
+    fn toffee_data_entries() {
+        let data = toffee_data("test", TestBackend.entries(""));
+        assert_eq!(data.entries.len(), 1);
+        assert_eq!(data.counter, Some((1, 1)));
+        assert_eq!(data.error, None);
+    }
+
+    #[test]
+    fn toffee_data_error() {
+        let data = toffee_data("test", TestBackend.entries("fail"));
+        assert!(data.entries.is_empty());
+        assert_eq!(data.counter, None);
+        assert_eq!(data.error.as_deref(), Some("listing failed: no entries"));
+    }
+
+    #[test]
+    fn read_font_missing() {
+        let directory = tempfile::tempdir().unwrap();
+        assert!(read_font(&directory.path().join("missing.ttf")).is_none());
+    }
+
+    #[test]
+    fn read_font_invalid() {
+        let directory = tempfile::tempdir().unwrap();
+        let path = directory.path().join("font.ttf");
+        fs::write(&path, b"font").unwrap();
+        assert!(read_font(&path).is_none());
+    }
+
+    #[test]
+    fn read_font_present() {
+        let directory = tempfile::tempdir().unwrap();
+        let path = directory.path().join("font.ttf");
+        let font = &egui::FontDefinitions::default().font_data["Hack"].font;
+        fs::write(&path, font).unwrap();
+        assert_eq!(read_font(&path).unwrap().font, *font);
+    }
+}
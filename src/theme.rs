@@ -0,0 +1,94 @@
+use eframe::egui::Color32;
+use serde::{de, Deserialize, Deserializer};
+
+/// Colours used to draw the list of entries, deserialized from hex strings like `"#104259"`.
+#[derive(Deserialize, Debug, Clone, Copy)]
+#[serde(default)]
+pub struct Theme {
+    #[serde(deserialize_with = "deserialize_color")]
+    pub selected: Color32,
+    #[serde(deserialize_with = "deserialize_color")]
+    pub even: Color32,
+    #[serde(deserialize_with = "deserialize_color")]
+    pub odd: Color32,
+    #[serde(deserialize_with = "deserialize_color")]
+    pub text: Color32,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self {
+            selected: Color32::from_rgb(0x10, 0x42, 0x59),
+            even: Color32::from_gray(27),
+            odd: Color32::from_gray(35),
+            // egui's default (dark mode) label colour
+            text: Color32::from_gray(140),
+        }
+    }
+}
+
+/// Parses a colour of the form `#rrggbb`.
+fn parse_hex_color(s: &str) -> Option<Color32> {
+    let hex = s.strip_prefix('#')?;
+    if hex.len() != 6 || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+
+    let component = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).ok();
+    Some(Color32::from_rgb(
+        component(0)?,
+        component(2)?,
+        component(4)?,
+    ))
+}
+
+fn deserialize_color<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Color32, D::Error> {
+    let s = String::deserialize(deserializer)?;
+    parse_hex_color(&s)
+        .ok_or_else(|| de::Error::custom(format!("invalid colour {s:?}, expected #rrggbb")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_hex_color_valid() {
+        assert_eq!(
+            parse_hex_color("#104259"),
+            Some(Color32::from_rgb(0x10, 0x42, 0x59))
+        );
+        // in either case
+        assert_eq!(
+            parse_hex_color("#aBcDeF"),
+            Some(Color32::from_rgb(0xab, 0xcd, 0xef))
+        );
+        assert_eq!(parse_hex_color("#000000"), Some(Color32::BLACK));
+    }
+
+    #[test]
+    fn parse_hex_color_malformed() {
+        // the # is required
+        assert_eq!(parse_hex_color("104259"), None);
+        // exactly six digits are required
+        assert_eq!(parse_hex_color("#"), None);
+        assert_eq!(parse_hex_color("#fff"), None);
+        assert_eq!(parse_hex_color("#1042590"), None);
+        assert_eq!(parse_hex_color("#10425"), None);
+        // and they must be hex digits
+        assert_eq!(parse_hex_color("#10425g"), None);
+        assert_eq!(parse_hex_color("#+10425"), None);
+        // multi-byte characters aren't split
+        assert_eq!(parse_hex_color("#1042é"), None);
+    }
+
+    #[test]
+    fn deserialize_partial() {
+        let theme: Theme = toml::from_str(r##"selected = "#ff0000""##).unwrap();
+        assert_eq!(theme.selected, Color32::from_rgb(0xff, 0, 0));
+        // unset colours are the defaults
+        assert_eq!(theme.even, Theme::default().even);
+
+        assert!(toml::from_str::<Theme>(r#"odd = "red""#).is_err());
+    }
+}
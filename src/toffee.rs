@@ -1,33 +1,58 @@
 use eframe::egui;
 
-pub struct ToffeeOutput<Entry: Copy> {
+use crate::keymap::{Action, Keymap};
+use crate::theme::Theme;
+
+pub struct ToffeeOutput<Entry: Clone> {
     pub input_changed: bool,
     pub selected_entry: Option<Entry>,
+    /// Whether the entry was selected with the alternate action (i.e. while holding shift).
+    pub alternate: bool,
+    /// Whether a cancel key (Escape by default) was pressed, i.e. the launcher should be closed.
+    pub escaped: bool,
+    /// Whether a reload key (F5 by default) was pressed, i.e. the entries should be read again.
+    pub reload: bool,
 }
 
-impl<Entry: Copy> ToffeeOutput<Entry> {
+impl<Entry: Clone> ToffeeOutput<Entry> {
     pub fn input_changed(&self) -> bool {
         self.input_changed
     }
 
     pub fn selected_entry(&self) -> Option<Entry> {
-        self.selected_entry
+        self.selected_entry.clone()
     }
 }
 
-pub struct ToffeeData<'data, Entry: Copy> {
+/// Identifies an entry across changes to the list of entries (e.g. when the query changes), so the
+/// selection can stay on the same entry.
+pub trait EntryId {
+    fn id(&self) -> egui::Id;
+}
+
+pub struct ToffeeData<'data, Entry: Clone + EntryId> {
     pub mode: &'data str,
+    /// Shown in the query input while it's empty.
+    pub prompt: Option<&'data str>,
     pub counter: Option<(usize, usize)>,
     pub entries: Vec<Entry>,
+    /// Shown below the query, e.g. when the entries couldn't be listed.
+    pub error: Option<String>,
 }
 
-pub struct Toffee<'data, 'input, Entry: Copy> {
+/// Number of entries moved by PageUp/PageDown, unless set with [`Toffee::page_size`].
+const DEFAULT_PAGE_SIZE: usize = 10;
+
+pub struct Toffee<'data, 'input, Entry: Clone + EntryId> {
     id: egui::Id,
     data: ToffeeData<'data, Entry>,
     input: &'input mut dyn egui::TextBuffer,
+    page_size: usize,
+    theme: Theme,
+    keymap: Keymap,
 }
 
-impl<'data, 'input, Entry: Copy> Toffee<'data, 'input, Entry> {
+impl<'data, 'input, Entry: Clone + EntryId> Toffee<'data, 'input, Entry> {
     pub fn new(
         id: impl Into<egui::Id>,
         data: ToffeeData<'data, Entry>,
@@ -37,76 +62,154 @@ impl<'data, 'input, Entry: Copy> Toffee<'data, 'input, Entry> {
             id: id.into(),
             data,
             input,
+            page_size: DEFAULT_PAGE_SIZE,
+            theme: Theme::default(),
+            keymap: Keymap::default(),
         }
     }
 
+    /// Sets the number of entries moved by PageUp/PageDown.
+    pub fn page_size(mut self, page_size: usize) -> Self {
+        self.page_size = page_size;
+        self
+    }
+
+    pub fn theme(mut self, theme: Theme) -> Self {
+        self.theme = theme;
+        self
+    }
+
+    /// Sets the keys bound to navigating, selecting and cancelling.
+    pub fn keymap(mut self, keymap: Keymap) -> Self {
+        self.keymap = keymap;
+        self
+    }
+
+    /// Sets the hint shown in the query input while it's empty (e.g. `Run:`).
+    pub fn prompt(mut self, prompt: &'data str) -> Self {
+        self.data.prompt = Some(prompt);
+        self
+    }
+
     fn selected_index(&self, ui: &egui::Ui) -> usize {
         ui.memory(|m| m.data.get_temp(self.id.with("selected_index")))
             .unwrap_or_default()
     }
 
+    fn selected_id(&self, ui: &egui::Ui) -> Option<egui::Id> {
+        ui.memory(|m| m.data.get_temp(self.id.with("selected_id")))
+    }
+
     fn set_selected_index(&self, ui: &mut egui::Ui, selected_index: usize) {
+        let selected_id = self.data.entries.get(selected_index).map(EntryId::id);
         ui.memory_mut(|m| {
             m.data
-                .insert_temp(self.id.with("selected_index"), selected_index)
+                .insert_temp(self.id.with("selected_index"), selected_index);
+            m.data.insert_temp(self.id.with("selected_id"), selected_id);
         });
     }
 
+    /// Reads how many rows the list was scrolled by while the pointer was over it, using the list's
+    /// position and row height from the previous frame.
+    fn scroll_rows(&self, ui: &mut egui::Ui) -> isize {
+        let list_rect: Option<egui::Rect> =
+            ui.memory(|m| m.data.get_temp(self.id.with("list_rect")));
+        let row_height: Option<f32> = ui.memory(|m| m.data.get_temp(self.id.with("row_height")));
+        let (Some(list_rect), Some(row_height)) = (list_rect, row_height) else {
+            return 0;
+        };
+
+        let scroll = ui.input(|i| {
+            let hovered = i
+                .pointer
+                .hover_pos()
+                .is_some_and(|pos| list_rect.contains(pos));
+            if hovered {
+                i.scroll_delta.y
+            } else {
+                0.0
+            }
+        });
+        if scroll == 0.0 {
+            return 0;
+        }
+
+        // carry partial rows over, so slow scrolling (e.g. on a touchpad) still moves the selection
+        let remainder: f32 = ui
+            .memory(|m| m.data.get_temp(self.id.with("scroll_remainder")))
+            .unwrap_or_default();
+        let (rows, remainder) = scroll_rows(remainder + scroll, row_height);
+        ui.memory_mut(|m| {
+            m.data
+                .insert_temp(self.id.with("scroll_remainder"), remainder)
+        });
+
+        rows
+    }
+
     fn update_selected_index(&mut self, ui: &mut egui::Ui) -> (usize, bool) {
         let initial_selected_index = self.selected_index(ui);
-        let selected_index = initial_selected_index;
+        let initial_selected_id = self.selected_id(ui);
 
         let entries_len = self.data.entries.len();
 
-        // jump to the top of the list if the selected index is out of bounds (e.g. after an entries
-        // update that makes the list shorter)
-        let selected_index = if selected_index < entries_len {
-            selected_index
-        } else {
-            0
-        };
-
-        // handle arrow key up/down, without going out of bounds
-        #[derive(PartialEq)]
-        enum Motion {
-            Up,
-            Down,
-        }
-
+        // follow the selected entry if it's moved (e.g. after an entries update that filters out
+        // entries before it), or jump to the top of the list if it's gone
+        let ids = self
+            .data
+            .entries
+            .iter()
+            .map(EntryId::id)
+            .collect::<Vec<_>>();
+        let selected_index = find_selected_index(initial_selected_id, &ids);
+
+        // handle keyboard navigation, without going out of bounds
         let motion = ui.input_mut(|i| {
-            if i.consume_key(egui::Modifiers::default(), egui::Key::ArrowUp) {
-                Some(Motion::Up)
-            } else if i.consume_key(egui::Modifiers::default(), egui::Key::ArrowDown) {
-                Some(Motion::Down)
-            } else {
-                None
-            }
+            [
+                (Action::Up, Motion::Up),
+                (Action::Down, Motion::Down),
+                (Action::PageUp, Motion::PageUp),
+                (Action::PageDown, Motion::PageDown),
+                (Action::Home, Motion::Home),
+                (Action::End, Motion::End),
+            ]
+            .into_iter()
+            .find(|(action, _)| self.keymap.consume(i, *action))
+            .map(|(_, motion)| motion)
+        });
+        // handle scrolling over the list by moving the selection, rather than scrolling freely
+        let motion = motion.or_else(|| match self.scroll_rows(ui) {
+            0 => None,
+            rows => Some(Motion::Scroll(rows)),
         });
         let selected_index = match motion {
-            // move up one entry
-            Some(Motion::Up) if selected_index != 0 => selected_index - 1,
-            // move down one entry (add one to the index rather than subtracting one from the length
-            // to avoid an underflow when there are no entries)
-            Some(Motion::Down) if selected_index + 1 != entries_len => selected_index + 1,
-            // don't move - no motion, or it would put us out of bounds
-            _ => selected_index,
+            Some(motion) => motion.apply(selected_index, entries_len, self.page_size),
+            None => selected_index,
         };
 
         // update state and pass selected index back to caller
         let selected_index_changed = selected_index != initial_selected_index;
-        if selected_index_changed {
+        if selected_index_changed || ids.get(selected_index) != initial_selected_id.as_ref() {
             self.set_selected_index(ui, selected_index);
         }
 
         (selected_index, selected_index_changed)
     }
 
+    /// Shows the launcher, where `entry_contents` shows an entry in the list and
+    /// `entry_completion` gives the text the query is completed to when Tab is pressed (if any).
     pub fn show(
         mut self,
         ui: &mut egui::Ui,
         entry_contents: impl Fn(&mut egui::Ui, Entry),
+        entry_completion: impl Fn(Entry) -> Option<String>,
     ) -> ToffeeOutput<Entry> {
         let (selected_index, selected_index_changed) = self.update_selected_index(ui);
+        let completion = self
+            .data
+            .entries
+            .get(selected_index)
+            .and_then(|entry| entry_completion(entry.clone()));
 
         let query = |ui: &mut egui::Ui| {
             egui::SidePanel::left(self.id.with("query_mode"))
@@ -127,10 +230,34 @@ impl<'data, 'input, Entry: Copy> Toffee<'data, 'input, Entry> {
                     });
             }
 
-            let query = ui.add_sized(
-                ui.available_size(),
-                egui::TextEdit::singleline(self.input).frame(false),
-            );
+            // complete the query to the selected entry, moving the cursor to the end. Tab is only
+            // consumed if there's something to complete to, as it otherwise moves focus
+            let query_id = self.id.with("query_input");
+            let completed = completion.is_some_and(|completion| {
+                if !ui.input_mut(|i| i.consume_key(egui::Modifiers::NONE, egui::Key::Tab)) {
+                    return false;
+                }
+
+                let cursor = complete(self.input, &completion);
+                let mut state =
+                    egui::text_edit::TextEditState::load(ui.ctx(), query_id).unwrap_or_default();
+                state.set_ccursor_range(Some(egui::text_edit::CCursorRange::one(
+                    egui::text::CCursor::new(cursor),
+                )));
+                state.store(ui.ctx(), query_id);
+                true
+            });
+
+            let mut query_edit = egui::TextEdit::singleline(self.input)
+                .id(query_id)
+                .frame(false);
+            if let Some(prompt) = self.data.prompt {
+                query_edit = query_edit.hint_text(prompt);
+            }
+            let mut query = ui.add_sized(ui.available_size(), query_edit);
+            if completed {
+                query.mark_changed();
+            }
             query.request_focus();
             query
         };
@@ -140,19 +267,34 @@ impl<'data, 'input, Entry: Copy> Toffee<'data, 'input, Entry> {
             .show_inside(ui, query)
             .inner;
 
+        if let Some(error) = &self.data.error {
+            egui::TopBottomPanel::top(self.id.with("error"))
+                .frame(egui::Frame::none())
+                .show_inside(ui, |ui| {
+                    ui.colored_label(ui.visuals().error_fg_color, error);
+                });
+        }
+
         let entries = |ui: &mut egui::Ui| {
             ui.set_min_width(ui.max_rect().width());
             ui.vertical(|ui| {
                 let mut double_clicked = false;
                 for (index, entry) in self.data.entries.iter().enumerate() {
-                    let container = EntryContainer::from_selected_index(index, selected_index)
-                        .show(ui, |ui| {
-                            entry_contents(ui, *entry);
-                        });
+                    let container =
+                        EntryContainer::from_selected_index(index, selected_index, self.theme)
+                            .show(ui, |ui| {
+                                entry_contents(ui, entry.clone());
+                            });
 
                     if selected_index_changed && selected_index == index {
                         container.response.scroll_to_me(None);
                     }
+                    if index == 0 {
+                        let row_height = container.response.rect.height();
+                        ui.memory_mut(|m| {
+                            m.data.insert_temp(self.id.with("row_height"), row_height)
+                        });
+                    }
                     if container.response.clicked() {
                         self.set_selected_index(ui, index);
                     }
@@ -169,24 +311,125 @@ impl<'data, 'input, Entry: Copy> Toffee<'data, 'input, Entry> {
                 // remove vertical gaps between each result
                 ui.style_mut().spacing.item_spacing.y = 0.0;
 
-                egui::ScrollArea::vertical()
+                // the list is scrolled by moving the selection (see `scroll_rows`), which keeps the
+                // selection visible
+                let output = egui::ScrollArea::vertical()
                     .scroll_bar_visibility(egui::scroll_area::ScrollBarVisibility::AlwaysVisible)
-                    .show(ui, entries)
-                    .inner
+                    .enable_scrolling(false)
+                    .show(ui, entries);
+                ui.memory_mut(|m| {
+                    m.data
+                        .insert_temp(self.id.with("list_rect"), output.inner_rect)
+                });
+
+                output.inner
             })
             .inner;
 
-        let enter_pressed = ui.input(|i| i.key_pressed(egui::Key::Enter));
-        let selected_index_valid = selected_index < self.data.entries.len();
-        let selected_entry = if (enter_pressed || entry_double_clicked) && selected_index_valid {
-            Some(self.data.entries[selected_index])
-        } else {
-            None
-        };
+        let keys = ui.input(|i| OutputKeys {
+            select: self.keymap.pressed(i, Action::Select),
+            shift: i.modifiers.shift,
+            cancel: self.keymap.pressed(i, Action::Cancel),
+            reload: self.keymap.pressed(i, Action::Reload),
+        });
+
+        build_output(
+            &self.data.entries,
+            selected_index,
+            query.changed(),
+            entry_double_clicked,
+            keys,
+        )
+    }
+}
 
-        ToffeeOutput {
-            input_changed: query.changed(),
-            selected_entry,
+/// Replaces the contents of `input` with `completion`, returning the character index of the end of
+/// the input.
+fn complete(input: &mut dyn egui::TextBuffer, completion: &str) -> usize {
+    input.delete_char_range(0..input.as_str().chars().count());
+    input.insert_text(completion, 0)
+}
+
+/// The state of the keys which affect a [`ToffeeOutput`].
+#[derive(Clone, Copy, Debug, Default)]
+struct OutputKeys {
+    select: bool,
+    shift: bool,
+    cancel: bool,
+    reload: bool,
+}
+
+/// Builds the output for a frame, where the entry at `selected_index` is selected if a key bound to
+/// [`Action::Select`] was pressed or it was double clicked.
+fn build_output<Entry: Clone>(
+    entries: &[Entry],
+    selected_index: usize,
+    input_changed: bool,
+    double_clicked: bool,
+    keys: OutputKeys,
+) -> ToffeeOutput<Entry> {
+    let selected_entry = if keys.select || double_clicked {
+        entries.get(selected_index).cloned()
+    } else {
+        None
+    };
+
+    ToffeeOutput {
+        input_changed,
+        selected_entry,
+        alternate: keys.shift,
+        escaped: keys.cancel,
+        reload: keys.reload,
+    }
+}
+
+/// Converts a vertical scroll delta (positive when scrolling up) into a number of rows to move the
+/// selection by (positive when moving down), along with the remaining scroll delta which was less
+/// than a full row.
+fn scroll_rows(scroll: f32, row_height: f32) -> (isize, f32) {
+    if row_height <= 0.0 {
+        return (0, 0.0);
+    }
+
+    let rows = (-scroll / row_height).trunc();
+    (rows as isize, scroll + rows * row_height)
+}
+
+/// Finds the index of the entry with `selected_id` in `ids`, or the first entry if it isn't present.
+fn find_selected_index(selected_id: Option<egui::Id>, ids: &[egui::Id]) -> usize {
+    selected_id
+        .and_then(|selected_id| ids.iter().position(|id| *id == selected_id))
+        .unwrap_or(0)
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Motion {
+    Up,
+    Down,
+    PageUp,
+    PageDown,
+    Home,
+    End,
+    /// Move by a number of rows, down if positive or up if negative.
+    Scroll(isize),
+}
+
+impl Motion {
+    /// Moves `selected_index` within a list of `entries_len` entries, stopping at either end of the
+    /// list.
+    fn apply(self, selected_index: usize, entries_len: usize, page_size: usize) -> usize {
+        // the last valid index, or zero when there are no entries
+        let last_index = entries_len.saturating_sub(1);
+
+        match self {
+            Motion::Up => selected_index.saturating_sub(1),
+            Motion::Down => (selected_index + 1).min(last_index),
+            Motion::PageUp => selected_index.saturating_sub(page_size),
+            Motion::PageDown => (selected_index + page_size).min(last_index),
+            Motion::Home => 0,
+            Motion::End => last_index,
+            Motion::Scroll(rows) if rows < 0 => selected_index.saturating_sub(rows.unsigned_abs()),
+            Motion::Scroll(rows) => (selected_index + rows.unsigned_abs()).min(last_index),
         }
     }
 }
@@ -211,16 +454,17 @@ impl EntryContainerFillStyle {
 
 struct EntryContainer {
     fill_style: EntryContainerFillStyle,
+    theme: Theme,
 }
 
 impl EntryContainer {
-    fn new(fill_style: EntryContainerFillStyle) -> Self {
-        Self { fill_style }
+    fn new(fill_style: EntryContainerFillStyle, theme: Theme) -> Self {
+        Self { fill_style, theme }
     }
 
-    fn from_selected_index(index: usize, selected_index: usize) -> Self {
+    fn from_selected_index(index: usize, selected_index: usize, theme: Theme) -> Self {
         let fill_style = EntryContainerFillStyle::from_selected_index(index, selected_index);
-        Self::new(fill_style)
+        Self::new(fill_style, theme)
     }
 
     fn show<R>(
@@ -229,9 +473,9 @@ impl EntryContainer {
         add_contents: impl FnOnce(&mut egui::Ui) -> R,
     ) -> egui::InnerResponse<R> {
         let fill = match self.fill_style {
-            EntryContainerFillStyle::Selected => egui::Color32::from_rgb(0x10, 0x42, 0x59),
-            EntryContainerFillStyle::Even => egui::Color32::from_gray(27),
-            EntryContainerFillStyle::Odd => egui::Color32::from_gray(35),
+            EntryContainerFillStyle::Selected => self.theme.selected,
+            EntryContainerFillStyle::Even => self.theme.even,
+            EntryContainerFillStyle::Odd => self.theme.odd,
         };
 
         let frame = egui::Frame::none()
@@ -239,6 +483,7 @@ impl EntryContainer {
             .fill(fill)
             .show(ui, |ui| {
                 ui.set_min_width(ui.max_rect().width());
+                ui.visuals_mut().override_text_color = Some(self.theme.text);
                 add_contents(ui)
             });
 
@@ -248,3 +493,191 @@ impl EntryContainer {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backends::test::TestEntry;
+
+    fn ids(names: &[&str]) -> Vec<egui::Id> {
+        names.iter().map(egui::Id::new).collect()
+    }
+
+    fn data(prompt: Option<&str>) -> ToffeeData<'_, TestEntry> {
+        ToffeeData {
+            mode: "drun",
+            prompt,
+            counter: None,
+            entries: vec![TestEntry],
+            error: None,
+        }
+    }
+
+    #[test]
+    fn prompt() {
+        let mut input = String::new();
+
+        // no prompt by default
+        let toffee = Toffee::new("toffee", data(None), &mut input);
+        assert_eq!(toffee.data.prompt, None);
+
+        // set with the builder...
+        let toffee = Toffee::new("toffee", data(None), &mut input).prompt("Run:");
+        assert_eq!(toffee.data.prompt, Some("Run:"));
+        // ... or passed through from the data
+        let toffee = Toffee::new("toffee", data(Some("Open:")), &mut input);
+        assert_eq!(toffee.data.prompt, Some("Open:"));
+    }
+
+    #[test]
+    fn selection_follows_entry() {
+        let all = ids(&["a", "b", "c", "d"]);
+        let selected = Some(egui::Id::new("c"));
+        assert_eq!(find_selected_index(selected, &all), 2);
+
+        // the list shrinks, moving the selected entry up...
+        let filtered = ids(&["c", "d"]);
+        assert_eq!(find_selected_index(selected, &filtered), 0);
+        // ... and grows again, moving it back down
+        assert_eq!(find_selected_index(selected, &all), 2);
+    }
+
+    #[test]
+    fn selection_resets_when_gone() {
+        let selected = Some(egui::Id::new("c"));
+
+        // the selected entry is filtered out, so go back to the top
+        let filtered = ids(&["a", "b"]);
+        assert_eq!(find_selected_index(selected, &filtered), 0);
+        assert_eq!(find_selected_index(selected, &[]), 0);
+        // ... as with no selection at all
+        assert_eq!(find_selected_index(None, &ids(&["a", "b", "c"])), 0);
+    }
+
+    #[test]
+    fn output_escape() {
+        let escape = OutputKeys {
+            cancel: true,
+            ..OutputKeys::default()
+        };
+        let output = build_output(&["a", "b"], 1, false, false, escape);
+        assert!(output.escaped);
+        assert_eq!(output.selected_entry, None);
+
+        // not set by other keys
+        let enter = OutputKeys {
+            select: true,
+            ..OutputKeys::default()
+        };
+        let output = build_output(&["a", "b"], 1, false, false, enter);
+        assert!(!output.escaped);
+        assert_eq!(output.selected_entry, Some("b"));
+    }
+
+    #[test]
+    fn output_selection() {
+        // double clicking selects, as does Enter
+        let output = build_output(&["a", "b"], 0, false, true, OutputKeys::default());
+        assert_eq!(output.selected_entry, Some("a"));
+        // nothing is selected without Enter or a double click
+        let output = build_output(&["a", "b"], 0, true, false, OutputKeys::default());
+        assert_eq!(output.selected_entry, None);
+        // or if the selection is out of bounds
+        let enter = OutputKeys {
+            select: true,
+            ..OutputKeys::default()
+        };
+        let output = build_output::<&str>(&[], 0, false, false, enter);
+        assert_eq!(output.selected_entry, None);
+    }
+
+    #[test]
+    fn complete_replaces_input() {
+        let mut input = "fi".to_string();
+        assert_eq!(complete(&mut input, "Firefox"), 7);
+        assert_eq!(input, "Firefox");
+
+        // the whole input is replaced, even if it's longer than the completion
+        let mut input = "firefox --new-window".to_string();
+        assert_eq!(complete(&mut input, "Firefox"), 7);
+        assert_eq!(input, "Firefox");
+
+        // the cursor is counted in characters, not bytes
+        let mut input = String::new();
+        assert_eq!(complete(&mut input, "Résumé"), 6);
+        assert_eq!(input, "Résumé");
+    }
+
+    #[test]
+    fn motion_up_down() {
+        assert_eq!(Motion::Up.apply(5, 25, 10), 4);
+        assert_eq!(Motion::Down.apply(5, 25, 10), 6);
+        // stops at the ends of the list
+        assert_eq!(Motion::Up.apply(0, 25, 10), 0);
+        assert_eq!(Motion::Down.apply(24, 25, 10), 24);
+    }
+
+    #[test]
+    fn motion_page() {
+        assert_eq!(Motion::PageUp.apply(15, 25, 10), 5);
+        assert_eq!(Motion::PageDown.apply(5, 25, 10), 15);
+        // clamps to the ends of the list rather than not moving
+        assert_eq!(Motion::PageUp.apply(5, 25, 10), 0);
+        assert_eq!(Motion::PageDown.apply(20, 25, 10), 24);
+        assert_eq!(Motion::PageUp.apply(0, 25, 10), 0);
+        assert_eq!(Motion::PageDown.apply(24, 25, 10), 24);
+        // page size is configurable
+        assert_eq!(Motion::PageDown.apply(5, 25, 3), 8);
+    }
+
+    #[test]
+    fn motion_home_end() {
+        assert_eq!(Motion::Home.apply(12, 25, 10), 0);
+        assert_eq!(Motion::End.apply(12, 25, 10), 24);
+        assert_eq!(Motion::Home.apply(0, 25, 10), 0);
+        assert_eq!(Motion::End.apply(24, 25, 10), 24);
+    }
+
+    #[test]
+    fn motion_scroll() {
+        assert_eq!(Motion::Scroll(3).apply(5, 25, 10), 8);
+        assert_eq!(Motion::Scroll(-3).apply(5, 25, 10), 2);
+        // clamps to the ends of the list
+        assert_eq!(Motion::Scroll(-10).apply(5, 25, 10), 0);
+        assert_eq!(Motion::Scroll(10).apply(20, 25, 10), 24);
+    }
+
+    #[test]
+    fn scroll_rows_whole() {
+        // scrolling down moves the selection down...
+        assert_eq!(scroll_rows(-40.0, 20.0), (2, 0.0));
+        // ... and scrolling up moves it up
+        assert_eq!(scroll_rows(60.0, 20.0), (-3, 0.0));
+    }
+
+    #[test]
+    fn scroll_rows_partial() {
+        // partial rows are left over
+        assert_eq!(scroll_rows(-50.0, 20.0), (2, -10.0));
+        assert_eq!(scroll_rows(50.0, 20.0), (-2, 10.0));
+        assert_eq!(scroll_rows(-5.0, 20.0), (0, -5.0));
+        // nothing happens without a row height
+        assert_eq!(scroll_rows(-50.0, 0.0), (0, 0.0));
+    }
+
+    #[test]
+    fn motion_no_entries() {
+        for motion in [
+            Motion::Up,
+            Motion::Down,
+            Motion::PageUp,
+            Motion::PageDown,
+            Motion::Home,
+            Motion::End,
+            Motion::Scroll(-1),
+            Motion::Scroll(1),
+        ] {
+            assert_eq!(motion.apply(0, 0, 10), 0);
+        }
+    }
+}